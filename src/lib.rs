@@ -0,0 +1,5 @@
+//! Password manager terenkripsi dengan antarmuka terminal.
+//!
+//! Logika vault tersedia di modul [`vault`] agar bisa dipakai tanpa TUI.
+
+pub mod vault;
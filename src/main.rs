@@ -1,35 +1,79 @@
+use dotenv::dotenv;
 use std::{
     env,
     error::Error,
-    fs::{File, OpenOptions},
-    io::{self, BufRead, BufReader, Write},
-    path::Path,
-    time::Duration,
+    fs::{self, File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
-use dotenv::dotenv;
 
-use aes_gcm::{aead::Aead, aead::KeyInit, Aes256Gcm, Nonce};
+use arboard::Clipboard;
 use base64::{engine::general_purpose, Engine as _};
+use clap::{Parser, Subcommand};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    cursor,
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use rand::RngCore;
+use directories::ProjectDirs;
+use hmac::{Hmac, Mac};
+use password_manager::vault::{
+    corrupt_backup_path, decode_encrypted_components, decrypt_password, derive_key, encrypt_notes,
+    encrypt_password, export_token, file_mtime, import_token, list_backups, load_entries,
+    load_vault, load_warning, lock_vault, normalize_account, parse_csv_records, parse_tags,
+    read_salt, reencrypt_entries, reencrypt_entries_with, replace_file, requested_algorithm,
+    salt_path, save_entries, unix_now, vault_algorithm, verify_sentinel, CipherAlgorithm, Entry,
+    PasswordDigests, PasswordHistory, PlaintextCache, TagFilter, Vault, VaultCipher,
+    FORMAT_VERSION, SECS_PER_DAY, VERIFY_SENTINEL, WRONG_PASSPHRASE,
+};
+use rand::{seq::SliceRandom, Rng};
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Position, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Wrap,
+    },
     Terminal,
 };
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
 use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use zeroize::{Zeroize, Zeroizing};
 
-#[derive(Debug, Clone)]
-struct Entry {
-    account: String,
-    password: String,
+/// Below this width the list and detail panes stack vertically.
+const NARROW_WIDTH: u16 = 60;
+
+/// Narrowest terminal the UI draws in; anything narrower only shows a resize hint.
+const MIN_WIDTH: u16 = 30;
+
+/// Shortest terminal the UI draws in; anything shorter only shows a resize hint.
+const MIN_HEIGHT: u16 = 20;
+
+fn age_label(entry: &Entry, now: u64) -> String {
+    match entry.age_days(now) {
+        Some(0) => "hari ini".to_string(),
+        Some(days) => format!("{} hari lalu", days),
+        None => "tidak diketahui".to_string(),
+    }
+}
+
+fn replaced_label(old: &PasswordHistory, now: u64) -> String {
+    match now.saturating_sub(old.replaced_at) / SECS_PER_DAY {
+        0 => "diganti hari ini".to_string(),
+        days => format!("diganti {} hari lalu", days),
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -45,495 +89,5116 @@ struct Feedback {
     kind: FeedbackKind,
 }
 
-enum InputMode {
-    Normal,
-    EditingAccount,
-    EditingPassword,
+#[derive(Debug, Clone, Copy)]
+struct GeneratorPolicy {
+    length: usize,
+    include_lowercase: bool,
+    include_symbols: bool,
+    include_digits: bool,
+    include_uppercase: bool,
 }
 
-struct App {
-    entries: Vec<Entry>,
-    selected: usize,
-    list_state: ratatui::widgets::ListState,
-    input_mode: InputMode,
-    account_input: String,
-    password_input: String,
-    feedback: Option<Feedback>,
-    cipher: Aes256Gcm,
+impl Default for GeneratorPolicy {
+    fn default() -> Self {
+        GeneratorPolicy {
+            length: 20,
+            include_lowercase: true,
+            include_symbols: true,
+            include_digits: true,
+            include_uppercase: true,
+        }
+    }
 }
 
-fn initialize_cipher() -> Result<Aes256Gcm, String> {
-    let passphrase = env::var("PASSWORD_MANAGER_KEY")
-        .map_err(|_| "Environment variable PASSWORD_MANAGER_KEY belum diset.".to_string())?;
-    if passphrase.trim().is_empty() {
-        return Err("PASSWORD_MANAGER_KEY tidak boleh kosong.".to_string());
+impl GeneratorPolicy {
+    fn from_env() -> Result<GeneratorPolicy, String> {
+        let mut policy = GeneratorPolicy::default();
+        if let Ok(value) = env::var("GENERATOR_LENGTH") {
+            policy.length = value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|length| (4..=128).contains(length))
+                .ok_or_else(|| format!("GENERATOR_LENGTH tidak valid (4-128): {}", value))?;
+        }
+        if let Ok(value) = env::var("GENERATOR_CHARSET") {
+            policy.include_lowercase = false;
+            policy.include_uppercase = false;
+            policy.include_digits = false;
+            policy.include_symbols = false;
+            for class in value.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+                match class {
+                    "lower" => policy.include_lowercase = true,
+                    "upper" => policy.include_uppercase = true,
+                    "digit" => policy.include_digits = true,
+                    "symbol" => policy.include_symbols = true,
+                    other => {
+                        return Err(format!(
+                        "GENERATOR_CHARSET tidak mengenal '{}' (pilih lower, upper, digit, symbol).",
+                        other
+                    ))
+                    }
+                }
+            }
+            if !(policy.include_lowercase
+                || policy.include_uppercase
+                || policy.include_digits
+                || policy.include_symbols)
+            {
+                return Err("GENERATOR_CHARSET harus berisi minimal satu jenis karakter.".into());
+            }
+        }
+        Ok(policy)
     }
-    let digest = Sha256::digest(passphrase.as_bytes());
-    Aes256Gcm::new_from_slice(&digest).map_err(|_| "Gagal menginisialisasi cipher.".to_string())
-}
-
-fn encrypt_password(cipher: &Aes256Gcm, plaintext: &str) -> Result<String, String> {
-    let mut nonce_bytes = [0u8; 12];
-    rand::thread_rng().fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from(nonce_bytes);
-    let ciphertext = cipher
-        .encrypt(&nonce, plaintext.as_bytes())
-        .map_err(|e| format!("Gagal mengenkripsi password: {}", e))?;
-    let encoded_nonce = general_purpose::STANDARD.encode(nonce_bytes);
-    let encoded_cipher = general_purpose::STANDARD.encode(ciphertext);
-    Ok(format!("{}:{}", encoded_nonce, encoded_cipher))
-}
-
-fn decode_encrypted_components(value: &str) -> Result<(Vec<u8>, Vec<u8>), String> {
-    let (nonce_b64, cipher_b64) = value
-        .split_once(':')
-        .ok_or_else(|| "Format data enkripsi tidak valid.".to_string())?;
-    let nonce_bytes = general_purpose::STANDARD
-        .decode(nonce_b64)
-        .map_err(|_| "Nonce terenkripsi tidak valid.".to_string())?;
-    if nonce_bytes.len() != 12 {
-        return Err("Panjang nonce tidak valid.".to_string());
-    }
-    let cipher_bytes = general_purpose::STANDARD
-        .decode(cipher_b64)
-        .map_err(|_| "Ciphertext terenkripsi tidak valid.".to_string())?;
-    Ok((nonce_bytes, cipher_bytes))
-}
-
-fn decrypt_password(cipher: &Aes256Gcm, value: &str) -> Result<String, String> {
-    let (nonce_bytes, cipher_bytes) = decode_encrypted_components(value)?;
-    let nonce_array: [u8; 12] = nonce_bytes
-        .as_slice()
-        .try_into()
-        .map_err(|_| "Nonce terenkripsi tidak valid.".to_string())?;
-    let nonce = Nonce::from(nonce_array);
-    let plaintext = cipher
-        .decrypt(&nonce, cipher_bytes.as_ref())
-        .map_err(|_| "Gagal mendekripsi password.".to_string())?;
-    String::from_utf8(plaintext).map_err(|_| "Password terdekripsi bukan UTF-8 valid.".to_string())
 }
 
-impl App {
-    fn new(entries: Vec<Entry>, cipher: Aes256Gcm) -> App {
-        let mut list_state = ratatui::widgets::ListState::default();
-        if !entries.is_empty() {
-            list_state.select(Some(0));
-        }
-        App {
-            entries,
-            selected: 0,
-            list_state,
-            input_mode: InputMode::Normal,
-            account_input: String::new(),
-            password_input: String::new(),
-            feedback: None,
-            cipher,
+const LOWERCASE_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGIT_CHARS: &[u8] = b"0123456789";
+const SYMBOL_CHARS: &[u8] = b"!@#$%^&*()-_=+[]{};:.?/<>~";
+
+fn generate_password(policy: &GeneratorPolicy) -> String {
+    let mut classes: Vec<&[u8]> = Vec::new();
+    if policy.include_lowercase {
+        classes.push(LOWERCASE_CHARS);
+    }
+    if policy.include_uppercase {
+        classes.push(UPPERCASE_CHARS);
+    }
+    if policy.include_digits {
+        classes.push(DIGIT_CHARS);
+    }
+    if policy.include_symbols {
+        classes.push(SYMBOL_CHARS);
+    }
+    let pool: Vec<u8> = classes.concat();
+    let mut rng = rand::thread_rng();
+    let length = policy.length.max(classes.len());
+
+    // Guarantee one character from every enabled class, then fill from the combined pool.
+    let mut chars: Vec<u8> = classes
+        .iter()
+        .map(|class| class[rng.gen_range(0..class.len())])
+        .collect();
+    while chars.len() < length {
+        chars.push(pool[rng.gen_range(0..pool.len())]);
+    }
+    chars.shuffle(&mut rng);
+    chars.into_iter().map(char::from).collect()
+}
+
+#[derive(Debug, Clone)]
+struct PassphrasePolicy {
+    word_count: usize,
+    separator: String,
+}
+
+impl Default for PassphrasePolicy {
+    fn default() -> Self {
+        PassphrasePolicy {
+            word_count: 5,
+            separator: "-".to_string(),
         }
     }
+}
 
-    fn set_feedback(&mut self, text: impl Into<String>, kind: FeedbackKind) {
-        self.feedback = Some(Feedback {
-            text: text.into(),
-            kind,
-        });
+// EFF large wordlist (7776 words), https://www.eff.org/dice, CC BY 3.0.
+const WORDLIST: &str = include_str!("wordlist.txt");
+
+fn generate_passphrase(policy: &PassphrasePolicy) -> String {
+    let words: Vec<&str> = WORDLIST.lines().collect();
+    let mut rng = rand::thread_rng();
+    (0..policy.word_count.max(1))
+        .filter_map(|_| words.choose(&mut rng).copied())
+        .collect::<Vec<_>>()
+        .join(&policy.separator)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct Theme {
+    #[serde(skip)]
+    name: String,
+    text: Color,
+    muted: Color,
+    accent: Color,
+    info: Color,
+    success: Color,
+    warning: Color,
+    error: Color,
+    highlight_fg: Color,
+    highlight_bg: Color,
+    border: Color,
+    title: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
     }
+}
 
-    fn next(&mut self) {
-        if self.entries.is_empty() {
-            return;
+impl Theme {
+    fn dark() -> Theme {
+        Theme {
+            name: "Gelap".to_string(),
+            text: Color::White,
+            muted: Color::DarkGray,
+            accent: Color::Yellow,
+            info: Color::Cyan,
+            success: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            highlight_fg: Color::White,
+            highlight_bg: Color::Blue,
+            border: Color::Reset,
+            title: Color::Reset,
         }
-        if self.selected < self.entries.len() - 1 {
-            self.selected += 1;
-        } else {
-            self.selected = 0;
+    }
+
+    fn light() -> Theme {
+        Theme {
+            name: "Terang".to_string(),
+            text: Color::Black,
+            muted: Color::Gray,
+            accent: Color::Blue,
+            info: Color::Blue,
+            success: Color::Green,
+            warning: Color::Magenta,
+            error: Color::Red,
+            highlight_fg: Color::White,
+            highlight_bg: Color::DarkGray,
+            border: Color::Black,
+            title: Color::Black,
         }
-        self.list_state.select(Some(self.selected));
     }
 
-    fn previous(&mut self) {
-        if self.entries.is_empty() {
-            return;
+    fn high_contrast() -> Theme {
+        Theme {
+            name: "Kontras Tinggi".to_string(),
+            text: Color::White,
+            muted: Color::Gray,
+            accent: Color::LightYellow,
+            info: Color::LightCyan,
+            success: Color::LightGreen,
+            warning: Color::LightYellow,
+            error: Color::LightRed,
+            highlight_fg: Color::Black,
+            highlight_bg: Color::LightYellow,
+            border: Color::White,
+            title: Color::LightYellow,
         }
-        if self.selected > 0 {
-            self.selected -= 1;
-        } else {
-            self.selected = self.entries.len() - 1;
+    }
+
+    fn block<'a>(&self, title: impl Into<Line<'a>>) -> Block<'a> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.border))
+            .title(title)
+            .title_style(Style::default().fg(self.title))
+    }
+}
+
+fn load_themes() -> (Vec<Theme>, Option<String>) {
+    let mut themes = vec![Theme::dark(), Theme::light(), Theme::high_contrast()];
+    let Some(path) = vault_dir().map(|dir| dir.join("theme.toml")) else {
+        return (themes, None);
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return (themes, None);
+    };
+    match toml::from_str::<Theme>(&content) {
+        Ok(mut custom) => {
+            custom.name = "Kustom".to_string();
+            themes.insert(0, custom);
+            (themes, None)
         }
-        self.list_state.select(Some(self.selected));
+        Err(e) => (
+            themes,
+            Some(format!(
+                "Tema di {} tidak valid: {}",
+                path.display(),
+                e.message()
+            )),
+        ),
     }
+}
 
-    fn add_entry(&mut self) -> Result<(), String> {
-        let new_entry = Entry {
-            account: self.account_input.trim().to_string(),
-            password: self.password_input.trim().to_string(),
-        };
-        if new_entry.account.is_empty() || new_entry.password.is_empty() {
-            return Err("Account atau password tidak boleh kosong.".to_string());
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strength {
+    Weak,
+    Medium,
+    Strong,
+}
+
+impl Strength {
+    fn label(self) -> &'static str {
+        match self {
+            Strength::Weak => "Weak",
+            Strength::Medium => "Medium",
+            Strength::Strong => "Strong",
+        }
+    }
+
+    fn color(self, theme: &Theme) -> Color {
+        match self {
+            Strength::Weak => theme.error,
+            Strength::Medium => theme.warning,
+            Strength::Strong => theme.success,
         }
-        let encrypted = encrypt_password(&self.cipher, &new_entry.password)?;
-        self.entries.push(Entry {
-            account: new_entry.account,
-            password: encrypted,
-        });
-        self.account_input.clear();
-        self.password_input.clear();
-        Ok(())
     }
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(r);
+const MAX_PASSWORD_SCORE: u32 = 8;
+const GOOD_PASSWORD_LENGTH: usize = 12;
 
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(popup_layout[1])[1]
+struct PasswordAnalysis {
+    length: usize,
+    lowercase: bool,
+    uppercase: bool,
+    digit: bool,
+    symbol: bool,
+    repeats: u32,
 }
 
-fn is_encrypted_format(value: &str) -> bool {
-    value.contains(':')
-        && value
-            .split_once(':')
-            .map(|(n, c)| n.len() > 0 && c.len() > 0)
-            .unwrap_or(false)
-}
-
-fn load_entries(path: &str, cipher: &Aes256Gcm) -> io::Result<(Vec<Entry>, bool)> {
-    let mut entries = Vec::new();
-    let mut updated = false;
-    if !Path::new(path).exists() {
-        return Ok((entries, updated));
-    }
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        let line = line?;
-        let parts: Vec<&str> = line.splitn(2, ',').collect();
-        if parts.len() == 2 {
-            let account = parts[0].to_string();
-            let raw_password = parts[1].to_string();
-            let password = if is_encrypted_format(&raw_password) {
-                raw_password
-            } else {
-                updated = true;
-                encrypt_password(cipher, &raw_password)
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e)))?
-            };
-            entries.push(Entry { account, password });
+impl PasswordAnalysis {
+    fn new(pw: &str) -> PasswordAnalysis {
+        PasswordAnalysis {
+            length: pw.chars().count(),
+            lowercase: pw.chars().any(|c| c.is_lowercase()),
+            uppercase: pw.chars().any(|c| c.is_uppercase()),
+            digit: pw.chars().any(|c| c.is_ascii_digit()),
+            symbol: pw.chars().any(|c| !c.is_alphanumeric()),
+            repeats: pw
+                .chars()
+                .zip(pw.chars().skip(1))
+                .filter(|(a, b)| a == b)
+                .count() as u32,
         }
     }
-    Ok((entries, updated))
-}
 
-fn save_entries(path: &str, entries: &[Entry]) -> io::Result<()> {
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(path)?;
-    for entry in entries {
-        writeln!(file, "{},{}", entry.account, entry.password)?;
+    fn checks(&self) -> [(&'static str, bool); 5] {
+        [
+            ("a-z", self.lowercase),
+            ("A-Z", self.uppercase),
+            ("0-9", self.digit),
+            ("simbol", self.symbol),
+            ("12+ karakter", self.length >= GOOD_PASSWORD_LENGTH),
+        ]
+    }
+
+    fn score(&self) -> u32 {
+        let length_score = match self.length {
+            0 => return 0,
+            1..=7 => 1,
+            8..=11 => 2,
+            12..=15 => 3,
+            _ => 4,
+        };
+        let classes = [self.lowercase, self.uppercase, self.digit, self.symbol]
+            .iter()
+            .filter(|present| **present)
+            .count() as u32;
+        (length_score + classes).saturating_sub(self.repeats.div_ceil(2))
     }
-    Ok(())
 }
 
-fn ui<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
-    terminal.draw(|f| {
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(2)
-            .constraints(
-                [
-                    Constraint::Length(3),
-                    Constraint::Length(3),
-                    Constraint::Min(8),
-                    Constraint::Length(4),
-                ]
-                .as_ref(),
-            )
-            .split(f.area());
+/// Minimum requirements for saved passwords; nothing is required unless configured.
+#[derive(Debug, Clone, Default)]
+struct PasswordPolicy {
+    min_length: usize,
+    require_lowercase: bool,
+    require_uppercase: bool,
+    require_digit: bool,
+    require_symbol: bool,
+}
 
-        let status_text = format!(
-            "Total Entri: {} | Mode: {}",
-            app.entries.len(),
-            match app.input_mode {
-                InputMode::Normal => "Normal",
-                InputMode::EditingAccount => "Input Account",
-                InputMode::EditingPassword => "Input Password",
+impl PasswordPolicy {
+    fn from_env() -> Result<PasswordPolicy, String> {
+        let mut policy = PasswordPolicy::default();
+        if let Ok(value) = env::var("PASSWORD_MIN_LENGTH") {
+            policy.min_length = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("PASSWORD_MIN_LENGTH tidak valid: {}", value))?;
+        }
+        if let Ok(value) = env::var("PASSWORD_REQUIRE") {
+            for class in value.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+                match class {
+                    "lower" => policy.require_lowercase = true,
+                    "upper" => policy.require_uppercase = true,
+                    "digit" => policy.require_digit = true,
+                    "symbol" => policy.require_symbol = true,
+                    other => {
+                        return Err(format!(
+                        "PASSWORD_REQUIRE tidak mengenal '{}' (pilih lower, upper, digit, symbol).",
+                        other
+                    ))
+                    }
+                }
             }
-        );
-        let status = Paragraph::new(status_text)
-            .style(Style::default().fg(Color::White))
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Password Manager - Status"),
-            );
-        f.render_widget(status, chunks[0]);
+        }
+        Ok(policy)
+    }
 
-        let (feedback_text, feedback_style) = match &app.feedback {
-            Some(feedback) => {
-                let color = match feedback.kind {
-                    FeedbackKind::Info => Color::Cyan,
-                    FeedbackKind::Success => Color::Green,
-                    FeedbackKind::Error => Color::Red,
-                };
-                (
-                    feedback.text.clone(),
-                    Style::default().fg(color).add_modifier(Modifier::BOLD),
-                )
+    fn unmet(&self, pw: &str) -> Vec<String> {
+        let analysis = PasswordAnalysis::new(pw);
+        let mut unmet = Vec::new();
+        if analysis.length < self.min_length {
+            unmet.push(format!("minimal {} karakter", self.min_length));
+        }
+        for (required, present, label) in [
+            (self.require_lowercase, analysis.lowercase, "huruf kecil"),
+            (self.require_uppercase, analysis.uppercase, "huruf besar"),
+            (self.require_digit, analysis.digit, "angka"),
+            (self.require_symbol, analysis.symbol, "simbol"),
+        ] {
+            if required && !present {
+                unmet.push(format!("butuh {}", label));
             }
-            None => (
-                "Gunakan panah atas/bawah untuk navigasi, tekan 'a' untuk menambah entri."
-                    .to_string(),
-                Style::default().fg(Color::DarkGray),
-            ),
-        };
-        let feedback = Paragraph::new(feedback_text)
-            .style(feedback_style)
-            .alignment(Alignment::Center)
-            .block(Block::default().borders(Borders::ALL).title("Notifikasi"));
-        f.render_widget(feedback, chunks[1]);
-
-        let main_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
-            .split(chunks[2]);
-
-        let items: Vec<ListItem> = app
-            .entries
-            .iter()
-            .map(|entry| {
-                ListItem::new(entry.account.clone()).style(Style::default().fg(Color::White))
-            })
-            .collect();
+        }
+        unmet
+    }
+}
 
-        let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Daftar Akun"))
-            .highlight_style(
-                Style::default()
-                    .bg(Color::Blue)
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            )
-            .highlight_symbol(">> ");
-        f.render_stateful_widget(list, main_chunks[0], &mut app.list_state);
+fn password_score(pw: &str) -> u32 {
+    PasswordAnalysis::new(pw).score()
+}
 
-        let detail_block = Block::default().borders(Borders::ALL).title("Detail Akun");
+fn password_strength(pw: &str) -> Strength {
+    match password_score(pw) {
+        0..=3 => Strength::Weak,
+        4..=5 => Strength::Medium,
+        _ => Strength::Strong,
+    }
+}
 
-        let detail_text = if let Some(entry) = app.entries.get(app.selected) {
-            let masked_password = "*".repeat(entry.password.len().min(32).max(1));
-            Text::from(vec![
-                Line::from(Span::styled(
-                    format!("Akun: {}", entry.account),
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD),
-                )),
-                Line::default(),
-                Line::from(format!(
-                    "Password terenkripsi (disembunyikan): {}",
-                    masked_password
-                )),
-                Line::from("Tekan 'v' untuk melihat password asli pada notifikasi."),
-            ])
+fn strength_checks_line(pw: &str, theme: &Theme) -> Line<'static> {
+    let mut spans = Vec::new();
+    for (label, met) in PasswordAnalysis::new(pw).checks() {
+        if !spans.is_empty() {
+            spans.push(Span::raw(" "));
+        }
+        let style = if met {
+            Style::default().fg(theme.success)
         } else {
-            Text::from(vec![
-                Line::from("Belum ada entri."),
-                Line::from("Tekan 'a' untuk menambahkan akun baru."),
-            ])
+            Style::default().fg(theme.muted).add_modifier(Modifier::DIM)
         };
-        let detail = Paragraph::new(detail_text)
-            .block(detail_block)
-            .alignment(Alignment::Left);
-        f.render_widget(detail, main_chunks[1]);
+        spans.push(Span::styled(
+            format!("{} {}", if met { "✓" } else { "·" }, label),
+            style,
+        ));
+    }
+    Line::from(spans)
+}
 
-        let instruction_lines = match app.input_mode {
-            InputMode::Normal => vec![
-                "[Navigasi] Panah Atas/Bawah",
-                "[Tambah] 'a'",
-                "[Lihat Password] 'v'",
-                "[Keluar] 'q'",
-            ],
-            InputMode::EditingAccount => vec![
-                "Masukkan nama akun.",
-                "Enter untuk lanjut ke input password.",
-                "Esc untuk membatalkan penambahan.",
-            ],
-            InputMode::EditingPassword => vec![
-                "Masukkan password.",
-                "Enter untuk menyimpan entri.",
-                "Esc untuk membatalkan penambahan.",
-            ],
-        };
+fn strength_line(pw: &str, theme: &Theme) -> Line<'static> {
+    let score = password_score(pw).min(MAX_PASSWORD_SCORE);
+    let strength = password_strength(pw);
+    Line::from(vec![
+        Span::raw("Kekuatan: "),
+        Span::styled(
+            "█".repeat(score as usize),
+            Style::default().fg(strength.color(theme)),
+        ),
+        Span::styled(
+            "░".repeat((MAX_PASSWORD_SCORE - score) as usize),
+            Style::default().fg(theme.muted),
+        ),
+        Span::styled(
+            format!(" {}", strength.label()),
+            Style::default()
+                .fg(strength.color(theme))
+                .add_modifier(Modifier::BOLD),
+        ),
+    ])
+}
 
-        let instruction_text = Text::from(
-            instruction_lines
-                .into_iter()
-                .map(Line::from)
-                .collect::<Vec<_>>(),
-        );
+/// Key for the A-Z/Z-A views: case-insensitive for any script, not just ASCII.
+fn account_sort_key(account: &str) -> String {
+    account.to_lowercase()
+}
 
-        let instruction = Paragraph::new(instruction_text)
-            .block(Block::default().borders(Borders::ALL).title("Instruksi"))
-            .alignment(Alignment::Left);
-        f.render_widget(instruction, chunks[3]);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortMode {
+    Insertion,
+    AccountAsc,
+    AccountDesc,
+}
 
-        match app.input_mode {
-            InputMode::EditingAccount | InputMode::EditingPassword => {
-                let area = centered_rect(60, 20, f.area());
-                f.render_widget(Clear, area);
+impl SortMode {
+    fn next(self) -> SortMode {
+        match self {
+            SortMode::Insertion => SortMode::AccountAsc,
+            SortMode::AccountAsc => SortMode::AccountDesc,
+            SortMode::AccountDesc => SortMode::Insertion,
+        }
+    }
 
-                let title = if let InputMode::EditingAccount = app.input_mode {
-                    "Entri Baru - Account"
-                } else {
-                    "Entri Baru - Password"
-                };
-                let (input_text, counter) = if let InputMode::EditingAccount = app.input_mode {
-                    (&app.account_input, app.account_input.chars().count())
-                } else {
-                    (&app.password_input, app.password_input.chars().count())
-                };
-                let popup_text = Text::from(vec![
-                    Line::from(input_text.clone()),
-                    Line::from(Span::styled(
-                        format!("Karakter: {}", counter),
-                        Style::default().fg(Color::DarkGray),
-                    )),
-                ]);
-                let input = Paragraph::new(popup_text)
-                    .block(Block::default().borders(Borders::ALL).title(title))
-                    .alignment(Alignment::Center);
-                f.render_widget(input, area);
-            }
-            _ => {}
+    fn label(self) -> &'static str {
+        match self {
+            SortMode::Insertion => "Urutan Input",
+            SortMode::AccountAsc => "A-Z",
+            SortMode::AccountDesc => "Z-A",
         }
-    })?;
-    Ok(())
+    }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    dotenv().ok();    
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Next,
+    Previous,
+    Add,
+    Delete,
+    Reveal,
+    Quit,
+    Search,
+}
 
-    let data_file = "passwords.txt";
-    let cipher = initialize_cipher()?;
-    let (entries, mutated) = load_entries(data_file, &cipher).unwrap_or_else(|err| {
-        eprintln!("Error memuat entri: {}", err);
-        (Vec::new(), false)
-    });
-    let mut app = App::new(entries, cipher);
-    if mutated {
-        if let Err(err) = save_entries(data_file, &app.entries) {
-            eprintln!("Error menyimpan ulang entri terenkripsi: {}", err);
+impl Action {
+    const ALL: [Action; 7] = [
+        Action::Next,
+        Action::Previous,
+        Action::Add,
+        Action::Delete,
+        Action::Reveal,
+        Action::Quit,
+        Action::Search,
+    ];
+
+    fn config_name(self) -> &'static str {
+        match self {
+            Action::Next => "next",
+            Action::Previous => "previous",
+            Action::Add => "add",
+            Action::Delete => "delete",
+            Action::Reveal => "reveal",
+            Action::Quit => "quit",
+            Action::Search => "search",
         }
     }
+}
 
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// Keys handled directly in normal mode that cannot be rebound to another action.
+const RESERVED_KEYS: [KeyCode; 17] = [
+    KeyCode::Char('c'),
+    KeyCode::Char('C'),
+    KeyCode::Char('g'),
+    KeyCode::Char('o'),
+    KeyCode::Char('s'),
+    KeyCode::Char('t'),
+    KeyCode::Char('e'),
+    KeyCode::Char('V'),
+    KeyCode::Char('H'),
+    KeyCode::Char('x'),
+    KeyCode::Char('N'),
+    KeyCode::Char('\''),
+    KeyCode::Esc,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+];
 
-    loop {
-        ui(&mut terminal, &mut app)?;
+#[derive(Debug, Clone)]
+struct Keymap {
+    bindings: Vec<(Action, Vec<KeyCode>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: vec![
+                (Action::Next, vec![KeyCode::Down]),
+                (Action::Previous, vec![KeyCode::Up]),
+                (Action::Add, vec![KeyCode::Char('a')]),
+                (Action::Delete, vec![KeyCode::Char('d')]),
+                (Action::Reveal, vec![KeyCode::Char('v')]),
+                (Action::Quit, vec![KeyCode::Char('q')]),
+                (Action::Search, vec![KeyCode::Char('/')]),
+            ],
+        }
+    }
+}
+
+impl Keymap {
+    fn vim() -> Keymap {
+        let mut keymap = Keymap::default();
+        keymap.bind(Action::Next, vec![KeyCode::Char('j'), KeyCode::Down]);
+        keymap.bind(Action::Previous, vec![KeyCode::Char('k'), KeyCode::Up]);
+        keymap
+    }
+
+    fn preset(name: &str) -> Result<Keymap, String> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "default" => Ok(Keymap::default()),
+            "vim" => Ok(Keymap::vim()),
+            other => Err(format!("Preset keymap '{}' tidak dikenal.", other)),
+        }
+    }
+
+    fn bind(&mut self, action: Action, keys: Vec<KeyCode>) {
+        if let Some((_, bound)) = self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            *bound = keys;
+        }
+    }
+
+    fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    fn action(&self, key: &KeyEvent) -> Option<Action> {
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return None;
+        }
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key.code))
+            .map(|(action, _)| *action)
+    }
+
+    fn label(&self, action: Action) -> String {
+        self.keys(action)
+            .iter()
+            .map(|code| key_label(*code))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let mut seen: Vec<(KeyCode, Action)> = Vec::new();
+        for (action, keys) in &self.bindings {
+            if keys.is_empty() {
+                return Err(format!(
+                    "Aksi '{}' tidak memiliki tombol.",
+                    action.config_name()
+                ));
+            }
+            for key in keys {
+                if RESERVED_KEYS.contains(key)
+                    || matches!(key, KeyCode::Char(c) if c.is_ascii_digit())
+                {
+                    return Err(format!(
+                        "Tombol {} untuk aksi '{}' sudah dipakai fitur lain.",
+                        key_label(*key),
+                        action.config_name()
+                    ));
+                }
+                if let Some((_, other)) = seen.iter().find(|(k, _)| k == key) {
+                    return Err(format!(
+                        "Tombol {} dipakai oleh aksi '{}' dan '{}'.",
+                        key_label(*key),
+                        other.config_name(),
+                        action.config_name()
+                    ));
+                }
+                seen.push((*key, *action));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct KeymapConfig {
+    preset: Option<String>,
+    bindings: std::collections::HashMap<String, Vec<String>>,
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    match name.to_lowercase().as_str() {
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "enter" => Some(KeyCode::Enter),
+        "esc" => Some(KeyCode::Esc),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        "delete" => Some(KeyCode::Delete),
+        "home" => Some(KeyCode::Home),
+        "end" => Some(KeyCode::End),
+        "pageup" => Some(KeyCode::PageUp),
+        "pagedown" => Some(KeyCode::PageDown),
+        "space" => Some(KeyCode::Char(' ')),
+        _ => None,
+    }
+}
+
+fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Spasi".to_string(),
+        KeyCode::Char(c) => format!("'{}'", c),
+        KeyCode::Up => "Panah Atas".to_string(),
+        KeyCode::Down => "Panah Bawah".to_string(),
+        KeyCode::Left => "Panah Kiri".to_string(),
+        KeyCode::Right => "Panah Kanan".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Builds the keymap from `KEYMAP_PRESET` or `keymap.toml` in the config dir, where
+/// `[bindings]` entries override the preset per action.
+fn load_keymap() -> Result<Keymap, String> {
+    let path = vault_dir().map(|dir| dir.join("keymap.toml"));
+    let config = match path.as_ref().and_then(|path| fs::read_to_string(path).ok()) {
+        Some(content) => toml::from_str::<KeymapConfig>(&content)
+            .map_err(|e| format!("keymap.toml tidak valid: {}", e.message()))?,
+        None => KeymapConfig::default(),
+    };
+    let preset = env::var("KEYMAP_PRESET")
+        .ok()
+        .or(config.preset)
+        .unwrap_or_default();
+    let mut keymap = Keymap::preset(&preset)?;
+    for (name, keys) in &config.bindings {
+        let action = Action::ALL
+            .into_iter()
+            .find(|action| action.config_name() == name.as_str())
+            .ok_or_else(|| format!("Aksi '{}' di keymap.toml tidak dikenal.", name))?;
+        let codes = keys
+            .iter()
+            .map(|key| parse_key(key).ok_or_else(|| format!("Tombol '{}' tidak dikenal.", key)))
+            .collect::<Result<Vec<_>, _>>()?;
+        keymap.bind(action, codes);
+    }
+    keymap.validate()?;
+    Ok(keymap)
+}
+
+enum InputMode {
+    Normal,
+    EditingAccount,
+    EditingUsername,
+    EditingPassword,
+    EditingUrl,
+    EditingNotes,
+    EditingTags,
+    EditingTotp,
+    ConfirmDelete,
+    ConfirmDuplicate,
+    ConfirmQuit,
+    Searching,
+    Locked,
+    VaultSwitcher,
+    VaultPassphrase,
+    DebugBlob,
+    PasswordHistory,
+    ShareToken,
+    NotesView,
+}
+
+struct SaveJob {
+    generation: u64,
+    path: String,
+    entries: Vec<Entry>,
+    cipher: VaultCipher,
+}
+
+struct SaveOutcome {
+    generation: u64,
+    result: Result<Option<SystemTime>, String>,
+}
+
+/// Writes vault snapshots off the UI thread. Jobs that queue up while a write is running are
+/// coalesced so only the newest snapshot gets written.
+fn spawn_save_worker() -> (Sender<SaveJob>, Receiver<SaveOutcome>) {
+    let (job_tx, job_rx) = mpsc::channel::<SaveJob>();
+    let (outcome_tx, outcome_rx) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(mut job) = job_rx.recv() {
+            while let Ok(newer) = job_rx.try_recv() {
+                job = newer;
+            }
+            let result = save_entries(&job.path, &job.entries, &job.cipher)
+                .map(|()| file_mtime(&job.path))
+                .map_err(|e| format!("Error menyimpan entri: {}", e));
+            let outcome = SaveOutcome {
+                generation: job.generation,
+                result,
+            };
+            if outcome_tx.send(outcome).is_err() {
+                break;
+            }
+        }
+    });
+    (job_tx, outcome_rx)
+}
+
+/// Deadlines for the time-based behaviours; `App::tick` fires whichever have passed.
+#[derive(Debug, Default)]
+struct Timers {
+    clipboard_clear: Option<Instant>,
+    reveal_hide: Option<Instant>,
+    auto_lock: Option<Instant>,
+    unlock_retry: Option<Instant>,
+}
+
+impl Timers {
+    fn due(deadline: &mut Option<Instant>, now: Instant) -> bool {
+        if deadline.is_some_and(|at| now >= at) {
+            *deadline = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct App {
+    entries: Vec<Entry>,
+    selected: usize,
+    list_state: ratatui::widgets::ListState,
+    input_mode: InputMode,
+    account_input: String,
+    username_input: String,
+    password_input: String,
+    url_input: String,
+    notes_input: String,
+    tags_input: String,
+    totp_input: String,
+    feedback: Option<Feedback>,
+    cipher: VaultCipher,
+    editing_index: Option<usize>,
+    clipboard: Option<Clipboard>,
+    clipboard_clear_after: Duration,
+    /// SHA-256 of the secret last placed on the clipboard, so clearing never wipes
+    /// something the user copied afterwards.
+    clipboard_digest: Option<[u8; 32]>,
+    generator_policy: GeneratorPolicy,
+    password_policy: PasswordPolicy,
+    policy_override: bool,
+    debug: bool,
+    passphrase_policy: PassphrasePolicy,
+    search_query: String,
+    /// The best matching field per entry index for the current query, computed once per filter.
+    search_hits: std::collections::HashMap<usize, (SearchField, usize)>,
+    index_input: String,
+    /// Set by `'`, so the next letter jumps to an account even if it is bound to an action.
+    jump_pending: bool,
+    filtered_indices: Vec<usize>,
+    sort_mode: SortMode,
+    revealed: bool,
+    reveal_after: Option<Duration>,
+    page_size: Option<usize>,
+    duplicate_policy: DuplicatePolicy,
+    duplicate_of: Option<usize>,
+    plaintext_cache: PlaintextCache,
+    password_digests: PasswordDigests,
+    data_file: String,
+    auto_lock_after: Option<Duration>,
+    passphrase_input: String,
+    dirty: bool,
+    tag_filter: Option<TagFilter>,
+    vault_choices: Vec<String>,
+    vault_list_state: ratatui::widgets::ListState,
+    history_list_state: ratatui::widgets::ListState,
+    password_max_age_days: Option<u64>,
+    hibp_dir: Option<PathBuf>,
+    audit_log: Option<File>,
+    themes: Vec<Theme>,
+    theme_index: usize,
+    keymap: Keymap,
+    list_area: Rect,
+    cursor_pos: usize,
+    vault_lock: Option<File>,
+    vault_mtime: Option<SystemTime>,
+    overwrite_external_edit: bool,
+    input_error: Option<String>,
+    share_token: Option<String>,
+    notes_scroll: u16,
+    show_password: bool,
+    failed_attempts: u32,
+    timers: Timers,
+    save_tx: Sender<SaveJob>,
+    save_rx: Receiver<SaveOutcome>,
+    save_generation: u64,
+    saved_generation: u64,
+}
+
+const UNLOCK_BACKOFF_BASE: Duration = Duration::from_millis(500);
+const UNLOCK_BACKOFF_MAX: Duration = Duration::from_secs(8);
+
+fn unlock_backoff(failed_attempts: u32) -> Duration {
+    UNLOCK_BACKOFF_BASE
+        .saturating_mul(1 << failed_attempts.saturating_sub(1).min(16))
+        .min(UNLOCK_BACKOFF_MAX)
+}
+
+fn env_passphrase() -> Option<Zeroizing<String>> {
+    env::var("PASSWORD_MANAGER_KEY").ok().map(Zeroizing::new)
+}
+
+fn read_passphrase() -> Result<Zeroizing<String>, String> {
+    let Some(passphrase) = env_passphrase() else {
+        return prompt_passphrase("Passphrase master: ");
+    };
+    if passphrase.trim().is_empty() {
+        return Err("PASSWORD_MANAGER_KEY tidak boleh kosong.".to_string());
+    }
+    Ok(passphrase)
+}
+
+/// `PASSWORD_MANAGER_CLIPBOARD_TTL` wins over the older `CLIPBOARD_CLEAR_SECS`.
+fn clipboard_clear_duration() -> Duration {
+    let secs = ["PASSWORD_MANAGER_CLIPBOARD_TTL", "CLIPBOARD_CLEAR_SECS"]
+        .iter()
+        .find_map(|name| env::var(name).ok()?.trim().parse::<u64>().ok())
+        .unwrap_or(15);
+    Duration::from_secs(secs)
+}
+
+fn password_max_age_days() -> Option<u64> {
+    let days = env::var("PASSWORD_MAX_AGE_DAYS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(180);
+    (days > 0).then_some(days)
+}
+
+fn open_audit_log() -> Result<Option<File>, String> {
+    let Some(path) = env::var("PASSWORD_MANAGER_AUDIT")
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map(Some)
+        .map_err(|e| format!("Gagal membuka audit log {}: {}", path, e))
+}
+
+fn hibp_dataset_dir() -> Option<PathBuf> {
+    env::var("HIBP_DATASET_DIR")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+}
+
+/// Looks the password up in a local copy of the HIBP range files (`<PREFIX>.txt`, one
+/// `SUFFIX:COUNT` per line), so only the 5-character hash prefix selects what gets read.
+fn pwned_count(dir: &Path, password: &str) -> Option<u64> {
+    let hash: String = Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+    let (prefix, suffix) = hash.split_at(5);
+    let content = fs::read_to_string(dir.join(format!("{}.txt", prefix)))
+        .or_else(|_| fs::read_to_string(dir.join(prefix)))
+        .ok()?;
+    content.lines().find_map(|line| {
+        let (candidate, count) = line.trim().split_once(':')?;
+        candidate
+            .eq_ignore_ascii_case(suffix)
+            .then(|| count.trim().parse().ok())
+            .flatten()
+    })
+}
+
+fn reveal_duration() -> Option<Duration> {
+    let secs = env::var("REVEAL_SECS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(10);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DuplicatePolicy {
+    Prompt,
+    Reject,
+}
+
+/// The entry (other than `skip`) named `account`, ignoring case, surrounding whitespace and
+/// Unicode normalization differences.
+fn find_account(entries: &[Entry], account: &str, skip: Option<usize>) -> Option<usize> {
+    let account = normalize_account(account).to_lowercase();
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| Some(*index) != skip)
+        .find(|(_, entry)| normalize_account(&entry.account).to_lowercase() == account)
+        .map(|(index, _)| index)
+}
+
+fn duplicate_policy() -> DuplicatePolicy {
+    match env::var("DUPLICATE_ACCOUNTS") {
+        Ok(value) if value.trim().eq_ignore_ascii_case("reject") => DuplicatePolicy::Reject,
+        _ => DuplicatePolicy::Prompt,
+    }
+}
+
+fn configured_page_size() -> Option<usize> {
+    env::var("PAGE_SIZE")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|size| *size > 0)
+}
+
+fn auto_lock_duration() -> Option<Duration> {
+    let minutes = env::var("AUTO_LOCK_MINUTES")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(5);
+    (minutes > 0).then(|| Duration::from_secs(minutes * 60))
+}
+
+/// Everything `App` takes from the environment and the config directory, read in one place so
+/// tests can build an `App` from explicit values instead.
+struct Settings {
+    themes: Vec<Theme>,
+    theme_error: Option<String>,
+    clipboard_clear_after: Duration,
+    reveal_after: Option<Duration>,
+    page_size: Option<usize>,
+    duplicate_policy: DuplicatePolicy,
+    auto_lock_after: Option<Duration>,
+    password_max_age_days: Option<u64>,
+    hibp_dir: Option<PathBuf>,
+}
+
+impl Settings {
+    fn from_env() -> Settings {
+        let (themes, theme_error) = load_themes();
+        Settings {
+            themes,
+            theme_error,
+            clipboard_clear_after: clipboard_clear_duration(),
+            reveal_after: reveal_duration(),
+            page_size: configured_page_size(),
+            duplicate_policy: duplicate_policy(),
+            auto_lock_after: auto_lock_duration(),
+            password_max_age_days: password_max_age_days(),
+            hibp_dir: hibp_dataset_dir(),
+        }
+    }
+}
+
+impl App {
+    fn new(entries: Vec<Entry>, cipher: VaultCipher, data_file: &str) -> App {
+        let clipboard = Clipboard::new().ok();
+        App::with_settings(entries, cipher, data_file, Settings::from_env(), clipboard)
+    }
+
+    fn with_settings(
+        entries: Vec<Entry>,
+        cipher: VaultCipher,
+        data_file: &str,
+        settings: Settings,
+        clipboard: Option<Clipboard>,
+    ) -> App {
+        let entry_count = entries.len();
+        let mut list_state = ratatui::widgets::ListState::default();
+        if !entries.is_empty() {
+            list_state.select(Some(0));
+        }
+        let (save_tx, save_rx) = spawn_save_worker();
+        let auto_lock_after = settings.auto_lock_after;
+        App {
+            entries,
+            selected: 0,
+            list_state,
+            input_mode: InputMode::Normal,
+            account_input: String::new(),
+            username_input: String::new(),
+            password_input: String::new(),
+            url_input: String::new(),
+            notes_input: String::new(),
+            tags_input: String::new(),
+            totp_input: String::new(),
+            feedback: settings.theme_error.map(|text| Feedback {
+                text,
+                kind: FeedbackKind::Error,
+            }),
+            cipher,
+            editing_index: None,
+            clipboard,
+            clipboard_clear_after: settings.clipboard_clear_after,
+            clipboard_digest: None,
+            generator_policy: GeneratorPolicy::default(),
+            password_policy: PasswordPolicy::default(),
+            policy_override: false,
+            debug: false,
+            passphrase_policy: PassphrasePolicy::default(),
+            search_query: String::new(),
+            search_hits: std::collections::HashMap::new(),
+            index_input: String::new(),
+            jump_pending: false,
+            filtered_indices: (0..entry_count).collect(),
+            sort_mode: SortMode::Insertion,
+            revealed: false,
+            reveal_after: settings.reveal_after,
+            page_size: settings.page_size,
+            duplicate_policy: settings.duplicate_policy,
+            duplicate_of: None,
+            plaintext_cache: PlaintextCache::default(),
+            password_digests: PasswordDigests::default(),
+            data_file: data_file.to_string(),
+            auto_lock_after,
+            passphrase_input: String::new(),
+            dirty: false,
+            tag_filter: None,
+            vault_choices: Vec::new(),
+            vault_list_state: ratatui::widgets::ListState::default(),
+            history_list_state: ratatui::widgets::ListState::default(),
+            password_max_age_days: settings.password_max_age_days,
+            hibp_dir: settings.hibp_dir,
+            audit_log: None,
+            themes: settings.themes,
+            theme_index: 0,
+            keymap: Keymap::default(),
+            list_area: Rect::default(),
+            cursor_pos: 0,
+            vault_lock: None,
+            vault_mtime: file_mtime(data_file),
+            overwrite_external_edit: false,
+            input_error: None,
+            share_token: None,
+            notes_scroll: 0,
+            show_password: false,
+            failed_attempts: 0,
+            timers: Timers {
+                auto_lock: auto_lock_after.map(|after| Instant::now() + after),
+                ..Timers::default()
+            },
+            save_tx,
+            save_rx,
+            save_generation: 0,
+            saved_generation: 0,
+        }
+    }
+
+    fn set_feedback(&mut self, text: impl Into<String>, kind: FeedbackKind) {
+        self.feedback = Some(Feedback {
+            text: text.into(),
+            kind,
+        });
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.list_state.selected().map(|_| self.selected)
+    }
+
+    fn selected_entry(&self) -> Option<&Entry> {
+        self.selected_index()
+            .and_then(|index| self.entries.get(index))
+    }
+
+    fn select_visible(&mut self, position: Option<usize>) {
+        match position.and_then(|pos| self.filtered_indices.get(pos).map(|&index| (pos, index))) {
+            Some((pos, index)) => {
+                if self.selected != index {
+                    self.hide_password();
+                }
+                self.selected = index;
+                self.list_state.select(Some(pos));
+            }
+            None => {
+                self.hide_password();
+                self.list_state.select(None);
+            }
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        self.search_hits = if self.search_query.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            let (query, cipher, cache) =
+                (&self.search_query, &self.cipher, &mut self.plaintext_cache);
+            self.entries
+                .iter()
+                .enumerate()
+                .filter_map(|(index, entry)| {
+                    search_match(query, entry, cipher, cache).map(|hit| (index, hit))
+                })
+                .collect()
+        };
+        self.filtered_indices = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                self.search_query.is_empty() || self.search_hits.contains_key(index)
+            })
+            .filter(|(_, entry)| {
+                self.tag_filter
+                    .as_ref()
+                    .is_none_or(|filter| entry.matches_tag(filter))
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let entries = &self.entries;
+        let account_key = |index: &usize| account_sort_key(&entries[*index].account);
+        match self.sort_mode {
+            SortMode::Insertion => {}
+            SortMode::AccountAsc => self.filtered_indices.sort_by_cached_key(account_key),
+            SortMode::AccountDesc => {
+                self.filtered_indices.sort_by_cached_key(account_key);
+                self.filtered_indices.reverse();
+            }
+        }
+        if !self.search_query.is_empty() {
+            // Stable, so entries that match equally well keep the chosen sort order.
+            let hits = &self.search_hits;
+            self.filtered_indices
+                .sort_by_key(|index| hits.get(index).copied());
+        }
+    }
+
+    fn theme(&self) -> &Theme {
+        &self.themes[self.theme_index]
+    }
+
+    fn cycle_theme(&mut self) {
+        self.theme_index = (self.theme_index + 1) % self.themes.len();
+        self.set_feedback(format!("Tema: {}", self.theme().name), FeedbackKind::Info);
+    }
+
+    fn cycle_sort(&mut self) {
+        self.sort_mode = self.sort_mode.next();
+        self.refresh_filter();
+    }
+
+    fn refresh_filter(&mut self) {
+        let current = self.selected_index();
+        self.apply_filter();
+        let position = current
+            .and_then(|index| self.filtered_indices.iter().position(|&i| i == index))
+            .or(if self.filtered_indices.is_empty() {
+                None
+            } else {
+                Some(0)
+            });
+        self.select_visible(position);
+    }
+
+    fn cycle_tag_filter(&mut self) {
+        let mut filters: Vec<TagFilter> = Vec::new();
+        for entry in &self.entries {
+            for tag in &entry.tags {
+                let filter = TagFilter::Tag(tag.clone());
+                if !filters.contains(&filter) {
+                    filters.push(filter);
+                }
+            }
+        }
+        filters.sort_by_key(|filter| filter.label().to_lowercase());
+        if self.entries.iter().any(|entry| entry.tags.is_empty()) {
+            filters.push(TagFilter::Untagged);
+        }
+        let next = match &self.tag_filter {
+            Some(current) => filters
+                .iter()
+                .position(|filter| filter == current)
+                .and_then(|position| filters.get(position + 1)),
+            None => filters.first(),
+        };
+        self.tag_filter = next.cloned();
+        match &self.tag_filter {
+            Some(filter) => {
+                let text = format!("Filter tag: {}", filter.label());
+                self.set_feedback(text, FeedbackKind::Info);
+            }
+            None => self.set_feedback("Filter tag dihapus.", FeedbackKind::Info),
+        }
+        self.refresh_filter();
+    }
+
+    /// While typing a query the top match is selected so the detail pane follows it.
+    fn update_search(&mut self) {
+        self.apply_filter();
+        self.select_visible((!self.filtered_indices.is_empty()).then_some(0));
+    }
+
+    /// Leaves search with the chosen entry still selected in the unfiltered list.
+    fn commit_search(&mut self) {
+        self.search_query.clear();
+        self.refresh_filter();
+        self.input_mode = InputMode::Normal;
+    }
+
+    fn clear_filter(&mut self) {
+        self.search_query.clear();
+        self.refresh_filter();
+    }
+
+    fn next(&mut self) {
+        let len = self.filtered_indices.len();
+        if len == 0 {
+            return;
+        }
+        let position = match self.list_state.selected() {
+            Some(pos) if pos + 1 < len => pos + 1,
+            _ => 0,
+        };
+        self.select_visible(Some(position));
+    }
+
+    fn previous(&mut self) {
+        let len = self.filtered_indices.len();
+        if len == 0 {
+            return;
+        }
+        let position = match self.list_state.selected() {
+            Some(pos) if pos > 0 && pos < len => pos - 1,
+            _ => len - 1,
+        };
+        self.select_visible(Some(position));
+    }
+
+    fn list_height(&self) -> usize {
+        self.list_area.height.saturating_sub(2) as usize
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        if !matches!(self.input_mode, InputMode::Normal | InputMode::Searching) {
+            return;
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollDown => self.next(),
+            MouseEventKind::ScrollUp => self.previous(),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let inner = self.list_area.inner(Margin {
+                    vertical: 1,
+                    horizontal: 1,
+                });
+                if !inner.contains(Position::new(mouse.column, mouse.row)) {
+                    return;
+                }
+                let row = (mouse.row - inner.y) as usize;
+                let position = row + self.list_state.offset();
+                let on_page = self.page_start().is_none() || row < self.page_size();
+                if on_page && position < self.filtered_indices.len() {
+                    self.select_visible(Some(position));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn jump_by(&mut self, delta: isize) {
+        let len = self.filtered_indices.len();
+        if len == 0 {
+            return;
+        }
+        let current = self.list_state.selected().unwrap_or(0) as isize;
+        let position = (current + delta).clamp(0, len as isize - 1) as usize;
+        self.select_visible(Some(position));
+    }
+
+    /// Selects the next visible account starting with `letter`, wrapping past the end.
+    fn jump_to_letter(&mut self, letter: char) {
+        let len = self.filtered_indices.len();
+        let start = self.list_state.selected().map_or(0, |pos| pos + 1);
+        let letter: Vec<char> = letter.to_lowercase().collect();
+        let found = (0..len).map(|offset| (start + offset) % len).find(|&pos| {
+            let account = &self.entries[self.filtered_indices[pos]].account;
+            account
+                .to_lowercase()
+                .chars()
+                .take(letter.len())
+                .eq(letter.iter().copied())
+        });
+        match found {
+            Some(pos) => self.select_visible(Some(pos)),
+            None => self.set_feedback(
+                format!(
+                    "Tidak ada akun yang diawali '{}'.",
+                    letter.iter().collect::<String>()
+                ),
+                FeedbackKind::Info,
+            ),
+        }
+    }
+
+    /// Ends a `'` jump: a letter jumps to the next account starting with it, any other key
+    /// cancels.
+    fn finish_jump(&mut self, code: KeyCode) {
+        self.jump_pending = false;
+        if let KeyCode::Char(letter) = code {
+            if letter.is_alphabetic() {
+                self.jump_to_letter(letter);
+            }
+        }
+    }
+
+    fn push_index_digit(&mut self, digit: char) {
+        if self.index_input.len() < 6 {
+            self.index_input.push(digit);
+        }
+    }
+
+    /// Selects the entry at the typed 1-based position in the visible list.
+    fn submit_index(&mut self, copy: bool) {
+        let input = std::mem::take(&mut self.index_input);
+        let len = self.filtered_indices.len();
+        match input.parse::<usize>() {
+            Ok(number) if (1..=len).contains(&number) => {
+                self.select_visible(Some(number - 1));
+                if copy {
+                    self.copy_selected_password();
+                }
+            }
+            _ if len == 0 => self.set_feedback("Daftar akun kosong.", FeedbackKind::Error),
+            _ => self.set_feedback(
+                format!("Nomor entri {} tidak ada (1-{}).", input, len),
+                FeedbackKind::Error,
+            ),
+        }
+    }
+
+    fn page_size(&self) -> usize {
+        self.page_size.unwrap_or_else(|| self.list_height()).max(1)
+    }
+
+    fn current_page(&self) -> (usize, usize) {
+        let page_size = self.page_size();
+        let selected = self.list_state.selected().unwrap_or(0);
+        (
+            selected / page_size + 1,
+            self.filtered_indices.len().div_ceil(page_size).max(1),
+        )
+    }
+
+    fn page_start(&self) -> Option<usize> {
+        (self.page_size() <= self.list_height())
+            .then(|| (self.current_page().0 - 1) * self.page_size())
+    }
+
+    fn page_down(&mut self) {
+        self.jump_by(self.page_size() as isize);
+    }
+
+    fn page_up(&mut self) {
+        self.jump_by(-(self.page_size() as isize));
+    }
+
+    fn first(&mut self) {
+        if !self.filtered_indices.is_empty() {
+            self.select_visible(Some(0));
+        }
+    }
+
+    fn last(&mut self) {
+        let len = self.filtered_indices.len();
+        if len > 0 {
+            self.select_visible(Some(len - 1));
+        }
+    }
+
+    fn reused_by(&mut self, password: &str, skip: Option<usize>) -> Option<String> {
+        let target = self.cipher.secret_digest(password).ok()?;
+        let (entries, cipher, digests) = (&self.entries, &self.cipher, &mut self.password_digests);
+        entries
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != skip)
+            .find(|(_, entry)| {
+                digests
+                    .digest(cipher, &entry.password)
+                    .is_ok_and(|digest| bool::from(digest.ct_eq(&target)))
+            })
+            .map(|(_, entry)| entry.account.clone())
+    }
+
+    fn add_entry(&mut self) -> Result<Vec<String>, String> {
+        let account = normalize_account(&self.account_input);
+        let password = Zeroizing::new(self.password_input.trim().to_string());
+        let previous = self
+            .editing_index
+            .and_then(|index| self.entries.get(index))
+            .cloned();
+        // An empty password while editing keeps the stored ciphertext untouched.
+        let keep_password = previous.is_some() && password.is_empty();
+        if account.is_empty() || (password.is_empty() && !keep_password) {
+            return Err("Account atau password tidak boleh kosong.".to_string());
+        }
+        if self.entry_exists(&account) {
+            return Err(format!("Akun '{}' sudah ada. Gunakan nama lain.", account));
+        }
+        let unmet = self.password_policy.unmet(&password);
+        if !keep_password && !unmet.is_empty() && !self.policy_override {
+            return Err(format!(
+                "Password belum memenuhi kebijakan: {}. Tekan Ctrl+F untuk tetap menyimpan.",
+                unmet.join(", ")
+            ));
+        }
+        let totp_secret = Zeroizing::new(normalize_totp_secret(&self.totp_input));
+        if !totp_secret.is_empty() && decode_base32(&totp_secret).is_none() {
+            return Err("Secret TOTP harus berupa base32 yang valid.".to_string());
+        }
+        let mut warnings = Vec::new();
+        if !keep_password {
+            if let Some(other) = self.reused_by(&password, self.editing_index) {
+                warnings.push(format!("password ini sudah dipakai oleh akun {}", other));
+            }
+            if let Some(count) = self
+                .hibp_dir
+                .as_deref()
+                .and_then(|dir| pwned_count(dir, &password))
+            {
+                warnings.push(format!(
+                    "password ini muncul {} kali di data kebocoran HIBP",
+                    count
+                ));
+            }
+        }
+        let encrypted = match &previous {
+            Some(previous) if keep_password => previous.password.clone(),
+            _ => encrypt_password(&self.cipher, &password)?,
+        };
+        let encrypted_totp = if totp_secret.is_empty() {
+            None
+        } else {
+            Some(encrypt_password(&self.cipher, &totp_secret)?)
+        };
+        let now = unix_now();
+        let created_at = previous.as_ref().map_or(now, |entry| entry.created_at);
+        let password_changed = match &previous {
+            Some(previous) if !keep_password => *self.decrypt(&previous.password)? != *password,
+            _ => false,
+        };
+        let mut entry = Entry {
+            account,
+            username: self.username_input.trim().to_string(),
+            password: encrypted,
+            url: self.url_input.trim().to_string(),
+            notes: encrypt_notes(&self.cipher, self.notes_input.trim())?,
+            created_at,
+            updated_at: now,
+            totp_secret: encrypted_totp,
+            tags: parse_tags(&self.tags_input),
+            history: Vec::new(),
+        };
+        if let Some(previous) = previous {
+            entry.history = previous.history;
+            if password_changed {
+                entry.push_history(previous.password, now);
+            }
+        }
+        match self.editing_index.take() {
+            Some(index) if index < self.entries.len() => {
+                self.entries[index] = entry;
+            }
+            _ => {
+                self.entries.push(entry);
+            }
+        }
+        self.refresh_filter();
+        self.clear_inputs();
+        Ok(warnings)
+    }
+
+    fn find_duplicate(&self, account: &str) -> Option<usize> {
+        find_account(&self.entries, account, self.editing_index)
+    }
+
+    /// Whether another entry (not the one being edited) already uses `account`, ignoring case,
+    /// surrounding whitespace and Unicode normalization differences.
+    fn entry_exists(&self, account: &str) -> bool {
+        self.find_duplicate(account).is_some()
+    }
+
+    fn check_duplicate(&mut self) -> bool {
+        let Some(existing) = self.find_duplicate(&self.account_input) else {
+            return false;
+        };
+        let account = self.entries[existing].account.clone();
+        // Renaming an entry onto another one would silently leave the original behind, so
+        // edits are always rejected and only new entries may be merged into the existing one.
+        if self.duplicate_policy == DuplicatePolicy::Prompt && self.editing_index.is_none() {
+            self.duplicate_of = Some(existing);
+            self.input_mode = InputMode::ConfirmDuplicate;
+        } else {
+            self.set_feedback(
+                format!("Akun '{}' sudah ada. Gunakan nama lain.", account),
+                FeedbackKind::Error,
+            );
+            self.focus_field(InputMode::EditingAccount);
+        }
+        true
+    }
+
+    fn update_duplicate(&mut self) {
+        self.editing_index = self.duplicate_of.take();
+        self.submit_entry();
+        // A rejected entry (policy or TOTP error) would otherwise leave this prompt open, and
+        // 'y' would only ask again; go back to the last field so the error can be fixed. The
+        // update stays confirmed through `editing_index`.
+        if matches!(self.input_mode, InputMode::ConfirmDuplicate) && self.editing_index.is_some() {
+            self.focus_field(InputMode::EditingTotp);
+        }
+    }
+
+    fn rename_duplicate(&mut self) {
+        self.duplicate_of = None;
+        self.set_feedback(
+            "Ganti nama akun atau tekan Esc untuk membatalkan.",
+            FeedbackKind::Info,
+        );
+        self.focus_field(InputMode::EditingAccount);
+    }
+
+    fn submit_entry(&mut self) {
+        if self.check_duplicate() {
+            return;
+        }
+        let editing = self.editing_index.is_some();
+        let new_password = !self.password_input.trim().is_empty();
+        let account = self.account_input.trim().to_string();
+        let warnings = match self.add_entry() {
+            Ok(warnings) => warnings,
+            Err(msg) => {
+                self.set_feedback(msg, FeedbackKind::Error);
+                return;
+            }
+        };
+        self.dirty = true;
+        if let Err(msg) = self.save() {
+            self.set_feedback(msg, FeedbackKind::Error);
+        } else if !warnings.is_empty() {
+            self.set_feedback(
+                format!("Entri disimpan, tetapi {}.", warnings.join(" dan ")),
+                FeedbackKind::Error,
+            );
+        } else if editing && new_password {
+            self.set_feedback(
+                "Entri berhasil diperbarui dan password dienkripsi ulang.",
+                FeedbackKind::Success,
+            );
+        } else if editing {
+            self.set_feedback(
+                "Entri berhasil diperbarui, password lama dipertahankan.",
+                FeedbackKind::Success,
+            );
+        } else {
+            self.set_feedback(
+                "Entri berhasil ditambahkan dan password terenkripsi.",
+                FeedbackKind::Success,
+            );
+        }
+        self.input_mode = InputMode::Normal;
+        self.audit(if editing { "edit" } else { "add" }, &account);
+    }
+
+    /// Appends one line per access; each write is synced so a crash cannot drop it.
+    fn audit(&mut self, action: &str, account: &str) {
+        let Some(file) = self.audit_log.as_mut() else {
+            return;
+        };
+        let line = format!(
+            "{} {} {} {:?}\n",
+            unix_now(),
+            action,
+            vault_name(&self.data_file),
+            account
+        );
+        if let Err(e) = file
+            .write_all(line.as_bytes())
+            .and_then(|_| file.sync_data())
+        {
+            self.set_feedback(
+                format!("Gagal menulis audit log: {}", e),
+                FeedbackKind::Error,
+            );
+        }
+    }
+
+    fn decrypt(&mut self, value: &str) -> Result<Zeroizing<String>, String> {
+        self.plaintext_cache.decrypt(&self.cipher, value)
+    }
+
+    fn start_edit(&mut self) -> Result<(), String> {
+        let index = self
+            .selected_index()
+            .ok_or_else(|| "Tidak ada entri yang dipilih.".to_string())?;
+        let entry = self.entries[index].clone();
+        let totp_secret = match &entry.totp_secret {
+            Some(secret) => self.decrypt(secret)?.to_string(),
+            None => String::new(),
+        };
+        self.account_input = entry.account.clone();
+        self.username_input = entry.username.clone();
+        // Left empty so the stored password is only decrypted again if it is replaced.
+        self.password_input.zeroize();
+        self.url_input = entry.url.clone();
+        self.notes_input = if entry.notes.is_empty() {
+            String::new()
+        } else {
+            self.decrypt(&entry.notes)?.to_string()
+        };
+        self.tags_input = entry.tags.join(", ");
+        self.totp_input = totp_secret;
+        self.editing_index = Some(index);
+        self.focus_field(InputMode::EditingAccount);
+        Ok(())
+    }
+
+    fn clear_inputs(&mut self) {
+        self.account_input.zeroize();
+        self.username_input.zeroize();
+        self.password_input.zeroize();
+        self.url_input.zeroize();
+        self.notes_input.zeroize();
+        self.tags_input.zeroize();
+        self.totp_input.zeroize();
+        self.show_password = false;
+        self.policy_override = false;
+        self.input_error = None;
+    }
+
+    fn active_input(&self) -> Option<&String> {
+        match self.input_mode {
+            InputMode::EditingAccount => Some(&self.account_input),
+            InputMode::EditingUsername => Some(&self.username_input),
+            InputMode::EditingPassword => Some(&self.password_input),
+            InputMode::EditingUrl => Some(&self.url_input),
+            InputMode::EditingNotes => Some(&self.notes_input),
+            InputMode::EditingTags => Some(&self.tags_input),
+            InputMode::EditingTotp => Some(&self.totp_input),
+            _ => None,
+        }
+    }
+
+    fn active_input_mut(&mut self) -> Option<&mut String> {
+        match self.input_mode {
+            InputMode::EditingAccount => Some(&mut self.account_input),
+            InputMode::EditingUsername => Some(&mut self.username_input),
+            InputMode::EditingPassword => Some(&mut self.password_input),
+            InputMode::EditingUrl => Some(&mut self.url_input),
+            InputMode::EditingNotes => Some(&mut self.notes_input),
+            InputMode::EditingTags => Some(&mut self.tags_input),
+            InputMode::EditingTotp => Some(&mut self.totp_input),
+            _ => None,
+        }
+    }
+
+    fn focus_field(&mut self, mode: InputMode) {
+        self.input_mode = mode;
+        self.input_error = None;
+        self.cursor_pos = self.active_input().map_or(0, |input| input.chars().count());
+    }
+
+    /// Moves on to `next` only when the current required field is filled in; otherwise the
+    /// popup stays open with an inline error.
+    fn advance_required(&mut self, next: InputMode) {
+        let keeps_password =
+            matches!(self.input_mode, InputMode::EditingPassword) && self.editing_index.is_some();
+        if !keeps_password
+            && self
+                .active_input()
+                .is_none_or(|input| input.trim().is_empty())
+        {
+            let field = match self.input_mode {
+                InputMode::EditingAccount => "Nama akun",
+                _ => "Password",
+            };
+            self.input_error = Some(format!("{} tidak boleh kosong.", field));
+            return;
+        }
+        self.focus_field(next);
+    }
+
+    fn edit_input(&mut self, code: KeyCode) {
+        self.input_error = None;
+        let cursor = self.cursor_pos;
+        let Some(input) = self.active_input_mut() else {
+            return;
+        };
+        let len = input.chars().count();
+        let cursor = cursor.min(len);
+        self.cursor_pos = match code {
+            KeyCode::Char(c) => {
+                input.insert(char_to_byte_index(input, cursor), c);
+                cursor + 1
+            }
+            KeyCode::Backspace if cursor > 0 => {
+                input.remove(char_to_byte_index(input, cursor - 1));
+                cursor - 1
+            }
+            KeyCode::Delete if cursor < len => {
+                input.remove(char_to_byte_index(input, cursor));
+                cursor
+            }
+            KeyCode::Left => cursor.saturating_sub(1),
+            KeyCode::Right => (cursor + 1).min(len),
+            KeyCode::Home => 0,
+            KeyCode::End => len,
+            _ => cursor,
+        };
+    }
+
+    fn move_cursor_line(&mut self, delta: isize) {
+        let Some(input) = self.active_input() else {
+            return;
+        };
+        let (row, column) = cursor_row_column(input, self.cursor_pos);
+        let line_lengths: Vec<usize> = input.split('\n').map(|line| line.chars().count()).collect();
+        let target = row as isize + delta;
+        if target < 0 || target as usize >= line_lengths.len() {
+            return;
+        }
+        let target = target as usize;
+        let line_start: usize = line_lengths[..target].iter().map(|len| len + 1).sum();
+        self.cursor_pos = line_start + column.min(line_lengths[target]);
+    }
+
+    fn paste_input(&mut self, text: &str) {
+        let cursor = self.cursor_pos;
+        let multiline = matches!(self.input_mode, InputMode::EditingNotes);
+        let Some(input) = self.active_input_mut() else {
+            return;
+        };
+        let pasted = if multiline {
+            text.replace("\r\n", "\n").replace('\r', "\n")
+        } else {
+            text.trim_end_matches(['\r', '\n'])
+                .replace("\r\n", " ")
+                .replace(['\r', '\n'], " ")
+        };
+        let cursor = cursor.min(input.chars().count());
+        input.insert_str(char_to_byte_index(input, cursor), &pasted);
+        self.cursor_pos = cursor + pasted.chars().count();
+    }
+
+    fn cancel_input(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.clear_inputs();
+        self.cursor_pos = 0;
+        self.editing_index = None;
+        self.duplicate_of = None;
+    }
+
+    fn reveal_selected(&mut self) {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return;
+        };
+        if self.revealed {
+            self.hide_password();
+            return;
+        }
+        match self.decrypt(&entry.password) {
+            Ok(_) => {
+                self.show_password();
+                self.audit("reveal", &entry.account);
+            }
+            Err(err) => self.set_feedback(err, FeedbackKind::Error),
+        }
+    }
+
+    fn copy_selected_password(&mut self) {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return;
+        };
+        let plain = match self.decrypt(&entry.password) {
+            Ok(plain) => plain,
+            Err(err) => {
+                self.set_feedback(err, FeedbackKind::Error);
+                return;
+            }
+        };
+        if self.copy_secret(&plain) {
+            self.set_feedback(
+                format!(
+                    "Password disalin ke clipboard, dibersihkan dalam {} detik.",
+                    self.clipboard_clear_after.as_secs()
+                ),
+                FeedbackKind::Success,
+            );
+            self.audit("copy", &entry.account);
+        } else {
+            self.show_password();
+            self.set_feedback(
+                format!(
+                    "Clipboard tidak tersedia, password {} ditampilkan di panel detail.",
+                    entry.account
+                ),
+                FeedbackKind::Info,
+            );
+            self.audit("reveal", &entry.account);
+        }
+    }
+
+    fn copy_selected_username(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if entry.username.is_empty() {
+            let text = format!("Akun {} tidak memiliki username.", entry.account);
+            self.set_feedback(text, FeedbackKind::Info);
+            return;
+        }
+        let username = entry.username.clone();
+        if self.copy_plain_text(&username) {
+            self.set_feedback("Username disalin ke clipboard.", FeedbackKind::Success);
+        } else {
+            self.set_feedback(
+                "Clipboard tidak tersedia, username tidak dapat disalin.",
+                FeedbackKind::Error,
+            );
+        }
+    }
+
+    fn open_password_history(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if entry.history.is_empty() {
+            let text = format!("Akun {} belum memiliki riwayat password.", entry.account);
+            self.set_feedback(text, FeedbackKind::Info);
+            return;
+        }
+        self.history_list_state.select(Some(0));
+        self.input_mode = InputMode::PasswordHistory;
+    }
+
+    fn open_notes_view(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if entry.notes.is_empty() {
+            let text = format!("Akun {} belum memiliki catatan.", entry.account);
+            self.set_feedback(text, FeedbackKind::Info);
+            return;
+        }
+        self.notes_scroll = 0;
+        self.input_mode = InputMode::NotesView;
+    }
+
+    fn move_history_selection(&mut self, forward: bool) {
+        let len = self.selected_entry().map_or(0, |entry| entry.history.len());
+        if len == 0 {
+            return;
+        }
+        let current = self.history_list_state.selected().unwrap_or(0);
+        let next = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+        self.history_list_state.select(Some(next));
+    }
+
+    fn selected_history(&self) -> Option<(usize, &PasswordHistory)> {
+        let index = self.selected_index()?;
+        let position = self.history_list_state.selected()?;
+        Some((index, self.entries[index].history.get(position)?))
+    }
+
+    fn copy_history_password(&mut self) {
+        let Some((index, old)) = self.selected_history() else {
+            return;
+        };
+        let (account, value) = (self.entries[index].account.clone(), old.password.clone());
+        let plain = match self.decrypt(&value) {
+            Ok(plain) => plain,
+            Err(err) => {
+                self.set_feedback(err, FeedbackKind::Error);
+                return;
+            }
+        };
+        if self.copy_secret(&plain) {
+            self.set_feedback(
+                format!(
+                    "Password lama disalin ke clipboard, dibersihkan dalam {} detik.",
+                    self.clipboard_clear_after.as_secs()
+                ),
+                FeedbackKind::Success,
+            );
+            self.audit("copy-history", &account);
+        } else {
+            self.set_feedback(
+                "Clipboard tidak tersedia, password lama tidak dapat disalin.",
+                FeedbackKind::Error,
+            );
+        }
+    }
+
+    /// Makes the selected old password current again; the replaced one moves into the history.
+    fn restore_history_password(&mut self) -> Result<String, String> {
+        let (index, _) = self
+            .selected_history()
+            .ok_or_else(|| "Tidak ada password lama yang dipilih.".to_string())?;
+        let position = self.history_list_state.selected().unwrap_or(0);
+        let now = unix_now();
+        let entry = &mut self.entries[index];
+        let old = entry.history.remove(position);
+        let current = std::mem::replace(&mut entry.password, old.password);
+        entry.push_history(current, now);
+        entry.updated_at = now;
+        let account = entry.account.clone();
+        self.hide_password();
+        self.dirty = true;
+        self.save()?;
+        self.audit("restore", &account);
+        Ok(account)
+    }
+
+    fn open_share_token(&mut self) {
+        if self.selected_entry().is_none() {
+            return;
+        }
+        self.passphrase_input.zeroize();
+        self.share_token = None;
+        self.input_mode = InputMode::ShareToken;
+    }
+
+    fn close_share_token(&mut self) {
+        self.passphrase_input.zeroize();
+        self.share_token = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Seals the selected entry under the typed one-time passphrase and copies the token.
+    fn export_selected_token(&mut self) {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return;
+        };
+        if self.passphrase_input.trim().is_empty() {
+            self.set_feedback("Passphrase token tidak boleh kosong.", FeedbackKind::Error);
+            return;
+        }
+        let token = export_token(&entry, &self.cipher, &self.passphrase_input);
+        self.passphrase_input.zeroize();
+        let token = match token {
+            Ok(token) => token,
+            Err(err) => {
+                self.set_feedback(err, FeedbackKind::Error);
+                return;
+            }
+        };
+        if self.copy_plain_text(&token) {
+            self.set_feedback(
+                "Token disalin ke clipboard. Kirim passphrase-nya lewat saluran lain.",
+                FeedbackKind::Success,
+            );
+        } else {
+            self.set_feedback(
+                "Clipboard tidak tersedia, salin token dari popup.",
+                FeedbackKind::Info,
+            );
+        }
+        self.share_token = Some(token);
+        self.audit("share", &entry.account);
+    }
+
+    /// Puts a secret on the clipboard and schedules it to be cleared.
+    fn copy_secret(&mut self, secret: &str) -> bool {
+        let copied = match self.clipboard.as_mut() {
+            Some(clipboard) => clipboard.set_text(secret).is_ok(),
+            None => false,
+        };
+        if copied {
+            self.clipboard_digest = Some(Sha256::digest(secret.as_bytes()).into());
+            self.timers.clipboard_clear = Some(Instant::now() + self.clipboard_clear_after);
+        }
+        copied
+    }
+
+    /// Puts non-secret text on the clipboard. It replaces any copied password, so there is
+    /// nothing left to clear and the pending clear is dropped.
+    fn copy_plain_text(&mut self, text: &str) -> bool {
+        let copied = match self.clipboard.as_mut() {
+            Some(clipboard) => clipboard.set_text(text).is_ok(),
+            None => false,
+        };
+        if copied {
+            self.clipboard_digest = None;
+            self.timers.clipboard_clear = None;
+        }
+        copied
+    }
+
+    /// Clears the clipboard only while it still holds the secret from `copy_secret`.
+    fn clear_clipboard(&mut self) {
+        self.timers.clipboard_clear = None;
+        let Some(digest) = self.clipboard_digest.take() else {
+            return;
+        };
+        if let Some(clipboard) = self.clipboard.as_mut() {
+            let current = clipboard.get_text().map(Zeroizing::new);
+            let ours = current.is_ok_and(|text| {
+                let current: [u8; 32] = Sha256::digest(text.as_bytes()).into();
+                bool::from(current.ct_eq(&digest))
+            });
+            if ours {
+                let _ = clipboard.clear();
+            }
+        }
+    }
+
+    fn show_password(&mut self) {
+        self.revealed = true;
+        self.timers.reveal_hide = self.reveal_after.map(|after| Instant::now() + after);
+    }
+
+    fn hide_password(&mut self) {
+        self.revealed = false;
+        self.timers.reveal_hide = None;
+    }
+
+    /// Fires every timer whose deadline is at or before `now`; called once per loop iteration.
+    fn tick(&mut self, now: Instant) {
+        if Timers::due(&mut self.timers.clipboard_clear, now) {
+            self.clear_clipboard();
+            self.set_feedback("Clipboard dibersihkan.", FeedbackKind::Info);
+        }
+        if Timers::due(&mut self.timers.reveal_hide, now) {
+            self.hide_password();
+            self.set_feedback("Password disembunyikan kembali.", FeedbackKind::Info);
+        }
+        if !matches!(self.input_mode, InputMode::Locked)
+            && Timers::due(&mut self.timers.auto_lock, now)
+        {
+            self.lock();
+        }
+        self.tick_unlock_backoff(now);
+    }
+
+    /// Restarts the inactivity countdown after user input.
+    fn touch(&mut self) {
+        self.timers.auto_lock = self.auto_lock_after.map(|after| Instant::now() + after);
+    }
+
+    fn lock(&mut self) {
+        self.cancel_input();
+        if self.timers.clipboard_clear.is_some() {
+            self.clear_clipboard();
+        }
+        self.hide_password();
+        self.plaintext_cache.clear();
+        self.feedback = None;
+        self.index_input.clear();
+        self.jump_pending = false;
+        self.passphrase_input.zeroize();
+        self.input_mode = InputMode::Locked;
+    }
+
+    fn unlock(&mut self) -> Result<(), String> {
+        if let Err(err) = self.check_unlock_allowed(Instant::now()) {
+            self.passphrase_input.zeroize();
+            return Err(err);
+        }
+        let salt = read_salt(&salt_path(&self.data_file))?;
+        let key = derive_key(&self.passphrase_input, &salt).map(Zeroizing::new);
+        self.passphrase_input.zeroize();
+        if !self.cipher.key_matches(key?.as_slice()) {
+            return Err(self.record_failed_unlock());
+        }
+        self.reset_failed_unlocks();
+        self.input_mode = InputMode::Normal;
+        self.touch();
+        Ok(())
+    }
+
+    fn unlock_wait_message(remaining: Duration) -> String {
+        format!(
+            "Coba lagi dalam {} detik.",
+            remaining.as_millis().div_ceil(1000)
+        )
+    }
+
+    fn check_unlock_allowed(&self, now: Instant) -> Result<(), String> {
+        match self.timers.unlock_retry {
+            Some(retry_at) if now < retry_at => {
+                Err(Self::unlock_wait_message(retry_at.duration_since(now)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record_failed_unlock(&mut self) -> String {
+        self.failed_attempts = self.failed_attempts.saturating_add(1);
+        let delay = unlock_backoff(self.failed_attempts);
+        self.timers.unlock_retry = Some(Instant::now() + delay);
+        format!("{} {}", WRONG_PASSPHRASE, Self::unlock_wait_message(delay))
+    }
+
+    fn reset_failed_unlocks(&mut self) {
+        self.failed_attempts = 0;
+        self.timers.unlock_retry = None;
+    }
+
+    fn tick_unlock_backoff(&mut self, now: Instant) {
+        let Some(retry_at) = self.timers.unlock_retry else {
+            return;
+        };
+        if !matches!(
+            self.input_mode,
+            InputMode::Locked | InputMode::VaultPassphrase
+        ) {
+            return;
+        }
+        if now >= retry_at {
+            self.timers.unlock_retry = None;
+            self.set_feedback("Silakan masukkan passphrase lagi.", FeedbackKind::Info);
+        } else {
+            let text = format!(
+                "{} {}",
+                WRONG_PASSPHRASE,
+                Self::unlock_wait_message(retry_at.duration_since(now))
+            );
+            self.set_feedback(text, FeedbackKind::Error);
+        }
+    }
+
+    fn open_vault_switcher(&mut self) {
+        if self.dirty {
+            self.set_feedback(
+                "Simpan perubahan (Ctrl+S) sebelum berganti vault.",
+                FeedbackKind::Error,
+            );
+            return;
+        }
+        self.vault_choices = list_vault_files(&self.data_file);
+        let current = self
+            .vault_choices
+            .iter()
+            .position(|path| *path == self.data_file);
+        self.vault_list_state.select(current.or(Some(0)));
+        self.input_mode = InputMode::VaultSwitcher;
+    }
+
+    fn move_vault_selection(&mut self, forward: bool) {
+        let len = self.vault_choices.len();
+        if len == 0 {
+            return;
+        }
+        let current = self.vault_list_state.selected().unwrap_or(0);
+        let next = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+        self.vault_list_state.select(Some(next));
+    }
+
+    fn switch_vault(&mut self) -> Result<(String, Option<String>), String> {
+        let path = self
+            .vault_list_state
+            .selected()
+            .and_then(|position| self.vault_choices.get(position))
+            .cloned()
+            .ok_or_else(|| "Tidak ada vault yang dipilih.".to_string())?;
+        self.wait_for_saves();
+        if let Err(err) = self.check_unlock_allowed(Instant::now()) {
+            self.passphrase_input.zeroize();
+            return Err(err);
+        }
+        let lock = if path == self.data_file {
+            None
+        } else {
+            match lock_vault(&path) {
+                Ok(lock) => Some(lock),
+                Err(err) => {
+                    self.passphrase_input.zeroize();
+                    return Err(err);
+                }
+            }
+        };
+        let loaded = load_vault(&path, &self.passphrase_input);
+        self.passphrase_input.zeroize();
+        let Vault {
+            entries,
+            cipher,
+            mutated,
+            corrupt,
+            tampered,
+        } = match loaded {
+            Ok(vault) => vault,
+            Err(err) if err == WRONG_PASSPHRASE => return Err(self.record_failed_unlock()),
+            Err(err) => return Err(err),
+        };
+        self.reset_failed_unlocks();
+        if self.timers.clipboard_clear.is_some() {
+            self.clear_clipboard();
+        }
+        self.entries = entries;
+        self.cipher = cipher;
+        self.plaintext_cache.clear();
+        self.password_digests.clear();
+        if let Some(old_lock) = lock.and_then(|lock| self.vault_lock.replace(lock)) {
+            let _ = old_lock.unlock();
+        }
+        self.vault_mtime = file_mtime(&path);
+        self.overwrite_external_edit = false;
+        self.data_file = path;
+        self.search_query.clear();
+        self.tag_filter = None;
+        self.hide_password();
+        self.dirty = false;
+        self.apply_filter();
+        self.select_visible(if self.filtered_indices.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+        self.input_mode = InputMode::Normal;
+        if mutated {
+            self.dirty = true;
+            self.save()?;
+        }
+        Ok((
+            vault_name(&self.data_file),
+            load_warning(&corrupt, tampered),
+        ))
+    }
+
+    fn open_selected_url(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let url = entry.url.trim().to_string();
+        if url.is_empty() {
+            self.set_feedback("Entri ini tidak memiliki URL.", FeedbackKind::Error);
+            return;
+        }
+        if !is_web_url(&url) {
+            self.set_feedback(
+                "URL harus diawali http:// atau https:// dan tidak boleh mengandung spasi.",
+                FeedbackKind::Error,
+            );
+            return;
+        }
+        match open::that_detached(&url) {
+            Ok(()) => self.set_feedback(format!("Membuka {}", url), FeedbackKind::Success),
+            Err(e) => self.set_feedback(format!("Gagal membuka URL: {}", e), FeedbackKind::Error),
+        }
+    }
+
+    fn delete_entry(&mut self) -> Result<Entry, String> {
+        let (Some(position), Some(index)) = (self.list_state.selected(), self.selected_index())
+        else {
+            return Err("Tidak ada entri yang dipilih.".to_string());
+        };
+        let removed = self.entries.remove(index);
+        self.apply_filter();
+        let len = self.filtered_indices.len();
+        self.select_visible(if len == 0 {
+            None
+        } else {
+            Some(position.min(len - 1))
+        });
+        self.dirty = true;
+        self.save()?;
+        Ok(removed)
+    }
+
+    /// Swaps the selected entry with its visible neighbour, changing the order on disk.
+    fn move_selected(&mut self, up: bool) {
+        let Some(position) = self.list_state.selected() else {
+            return;
+        };
+        if self.sort_mode != SortMode::Insertion {
+            self.set_feedback(
+                "Entri hanya dapat dipindahkan pada urutan \"Urutan Input\".",
+                FeedbackKind::Info,
+            );
+            return;
+        }
+        let target = if up {
+            position.checked_sub(1)
+        } else {
+            Some(position + 1)
+        };
+        let Some(target) = target.filter(|&target| target < self.filtered_indices.len()) else {
+            return;
+        };
+        let (from, to) = (
+            self.filtered_indices[position],
+            self.filtered_indices[target],
+        );
+        self.entries.swap(from, to);
+        self.apply_filter();
+        self.selected = to;
+        self.list_state
+            .select(self.filtered_indices.iter().position(|&index| index == to));
+        self.dirty = true;
+        if let Err(err) = self.save() {
+            self.set_feedback(err, FeedbackKind::Error);
+        }
+    }
+
+    fn save_in_flight(&self) -> bool {
+        self.saved_generation < self.save_generation
+    }
+
+    /// Queues a snapshot for the save worker; the result arrives through `poll_saves`.
+    fn save(&mut self) -> Result<(), String> {
+        // While a save is running the file's mtime is about to change because of our own write.
+        if !self.save_in_flight()
+            && file_mtime(&self.data_file) != self.vault_mtime
+            && !self.overwrite_external_edit
+        {
+            self.overwrite_external_edit = true;
+            self.dirty = true;
+            return Err(
+                "File vault diubah di luar aplikasi sejak dimuat. Tekan Ctrl+S untuk menimpanya."
+                    .to_string(),
+            );
+        }
+        self.save_generation += 1;
+        let job = SaveJob {
+            generation: self.save_generation,
+            path: self.data_file.clone(),
+            entries: self.entries.clone(),
+            cipher: self.cipher.clone(),
+        };
+        self.save_tx
+            .send(job)
+            .map_err(|_| "Proses penyimpanan vault berhenti.".to_string())?;
+        self.overwrite_external_edit = false;
+        self.password_digests.retain(&self.entries);
+        Ok(())
+    }
+
+    fn finish_save(&mut self, outcome: SaveOutcome) {
+        self.saved_generation = outcome.generation;
+        match outcome.result {
+            Ok(mtime) => {
+                self.vault_mtime = mtime;
+                if !self.save_in_flight() {
+                    self.dirty = false;
+                }
+            }
+            Err(msg) => {
+                self.dirty = true;
+                self.set_feedback(msg, FeedbackKind::Error);
+            }
+        }
+    }
+
+    fn poll_saves(&mut self) {
+        while let Ok(outcome) = self.save_rx.try_recv() {
+            self.finish_save(outcome);
+        }
+    }
+
+    fn wait_for_saves(&mut self) {
+        while self.save_in_flight() {
+            match self.save_rx.recv() {
+                Ok(outcome) => self.finish_save(outcome),
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn prepare_quit(&mut self) {
+        self.wait_for_saves();
+        if self.timers.clipboard_clear.is_some() {
+            self.clear_clipboard();
+        }
+    }
+}
+
+const TOTP_PERIOD: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+
+fn normalize_totp_secret(input: &str) -> String {
+    input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in input.trim_end_matches('=').chars() {
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    (!bytes.is_empty()).then_some(bytes)
+}
+
+fn totp_code(secret: &[u8], unix_time: u64) -> Result<u32, String> {
+    let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(secret)
+        .map_err(|_| "Secret TOTP tidak valid.".to_string())?;
+    mac.update(&(unix_time / TOTP_PERIOD).to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    Ok(binary % 10u32.pow(TOTP_DIGITS))
+}
+
+fn totp_line(secret: Result<Zeroizing<String>, String>, now: u64, theme: &Theme) -> Line<'static> {
+    let code = secret.and_then(|secret| {
+        let key = Zeroizing::new(
+            decode_base32(&secret).ok_or_else(|| "Secret TOTP tidak valid.".to_string())?,
+        );
+        totp_code(&key, now)
+    });
+    match code {
+        Ok(code) => Line::from(vec![
+            Span::raw("TOTP: "),
+            Span::styled(
+                format!("{:0width$}", code, width = TOTP_DIGITS as usize),
+                Style::default()
+                    .fg(theme.success)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(" (berlaku {} detik lagi)", TOTP_PERIOD - now % TOTP_PERIOD),
+                Style::default().fg(theme.muted),
+            ),
+        ]),
+        Err(err) => Line::from(Span::styled(
+            format!("TOTP: {}", err),
+            Style::default().fg(theme.error),
+        )),
+    }
+}
+
+fn char_to_byte_index(value: &str, char_index: usize) -> usize {
+    value
+        .char_indices()
+        .nth(char_index)
+        .map_or(value.len(), |(index, _)| index)
+}
+
+fn fuzzy_match(query: &str, candidate: &str) -> Option<Vec<usize>> {
+    let mut query_chars = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut positions = Vec::new();
+    for (position, c) in candidate.chars().enumerate() {
+        let Some(expected) = query_chars.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(std::iter::once(*expected)) {
+            positions.push(position);
+            query_chars.next();
+        }
+    }
+    query_chars.peek().is_none().then_some(positions)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SearchField {
+    Account,
+    Username,
+    Url,
+    Tags,
+    Notes,
+}
+
+impl SearchField {
+    fn label(self) -> &'static str {
+        match self {
+            SearchField::Account => "akun",
+            SearchField::Username => "username",
+            SearchField::Url => "url",
+            SearchField::Tags => "tag",
+            SearchField::Notes => "catatan",
+        }
+    }
+}
+
+/// The highest-ranked field of `entry` that fuzzily matches `query`, paired with how spread
+/// out the match is, so sorting by the result puts account matches and tight matches first.
+/// Notes are only decrypted, through the plaintext cache, when no plaintext field matched.
+fn search_match(
+    query: &str,
+    entry: &Entry,
+    cipher: &VaultCipher,
+    cache: &mut PlaintextCache,
+) -> Option<(SearchField, usize)> {
+    let spread = |positions: Vec<usize>| match (positions.first(), positions.last()) {
+        (Some(first), Some(last)) => last - first + 1 - positions.len(),
+        _ => 0,
+    };
+    let tags = entry.tags.join(", ");
+    let fields = [
+        (SearchField::Account, entry.account.as_str()),
+        (SearchField::Username, entry.username.as_str()),
+        (SearchField::Url, entry.url.as_str()),
+        (SearchField::Tags, tags.as_str()),
+    ];
+    for (field, text) in fields {
+        if let Some(positions) = fuzzy_match(query, text) {
+            return Some((field, spread(positions)));
+        }
+    }
+    if entry.notes.is_empty() {
+        return None;
+    }
+    let notes = cache.decrypt(cipher, &entry.notes).ok()?;
+    fuzzy_match(query, &notes).map(|positions| (SearchField::Notes, spread(positions)))
+}
+
+fn highlighted_line(text: &str, positions: &[usize], theme: &Theme) -> Line<'static> {
+    let spans: Vec<Span> = text
+        .chars()
+        .enumerate()
+        .map(|(position, c)| {
+            if positions.contains(&position) {
+                Span::styled(
+                    c.to_string(),
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Span::raw(c.to_string())
+            }
+        })
+        .collect();
+    Line::from(spans)
+}
+
+fn is_web_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    let Some(rest) = lower
+        .strip_prefix("https://")
+        .or_else(|| lower.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    !host.is_empty() && !url.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+fn display_or_dash(value: &str) -> &str {
+    if value.is_empty() {
+        "-"
+    } else {
+        value
+    }
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+fn blob_summary(value: &str) -> String {
+    match decode_encrypted_components(value) {
+        Ok(components) => format!(
+            "salt {}, nonce {} byte, ciphertext {} byte",
+            components
+                .salt
+                .map_or("- (format lama)".to_string(), |salt| format!(
+                    "{} byte",
+                    salt.len()
+                )),
+            components.nonce.len(),
+            components.ciphertext.len()
+        ),
+        Err(err) => err,
+    }
+}
+
+/// The mask shown for every stored password, so it says nothing about the password or
+/// ciphertext length.
+const PASSWORD_MASK: &str = "••••••••";
+
+/// Splits `text` into rows of at most `width` terminal columns, so a wide character is never
+/// cut in half and spaces stay where they are instead of becoming wrap points.
+fn split_by_width(text: &str, width: usize) -> Vec<&str> {
+    let width = width.max(2);
+    let mut rows = Vec::new();
+    let (mut start, mut used) = (0, 0);
+    for (index, ch) in text.char_indices() {
+        let char_width = ch.width().unwrap_or(0);
+        if used + char_width > width {
+            rows.push(&text[start..index]);
+            (start, used) = (index, 0);
+        }
+        used += char_width;
+    }
+    rows.push(&text[start..]);
+    rows
+}
+
+/// Zero-based line and column (in chars) of a char cursor inside multi-line text.
+fn cursor_row_column(text: &str, cursor: usize) -> (usize, usize) {
+    let before: String = text.chars().take(cursor).collect();
+    let row = before.matches('\n').count();
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count();
+    (row, column)
+}
+
+fn truncate_to_width(text: &str, width: u16) -> String {
+    let width = usize::from(width);
+    if Line::from(text).width() <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = Line::from(c.to_string()).width();
+        if used + char_width + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        used += char_width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+fn wrapped_height(text: &str, width: u16, max_lines: u16) -> u16 {
+    let lines = Line::from(text).width().div_ceil(usize::from(width.max(1)));
+    (lines as u16).clamp(1, max_lines)
+}
+
+fn ui<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    let theme = app.theme().clone();
+    terminal.draw(|f| {
+        let area = f.area();
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            app.list_area = Rect::default();
+            let message = Paragraph::new(format!(
+                "Terminal terlalu kecil ({}x{}). Perbesar ke minimal {}x{}.",
+                area.width, area.height, MIN_WIDTH, MIN_HEIGHT
+            ))
+            .style(Style::default().fg(theme.warning))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Length(3),
+                    Constraint::Fill(1),
+                ])
+                .split(area);
+            f.render_widget(message, rows[1]);
+            return;
+        }
+        let (feedback_text, feedback_style) = match &app.feedback {
+            Some(feedback) => {
+                let color = match feedback.kind {
+                    FeedbackKind::Info => theme.info,
+                    FeedbackKind::Success => theme.success,
+                    FeedbackKind::Error => theme.error,
+                };
+                (
+                    feedback.text.clone(),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                )
+            }
+            None => (
+                format!(
+                    "Gunakan {} / {} untuk navigasi, tekan {} untuk menambah entri.",
+                    app.keymap.label(Action::Previous),
+                    app.keymap.label(Action::Next),
+                    app.keymap.label(Action::Add)
+                ),
+                Style::default().fg(theme.muted),
+            ),
+        };
+        let mut indicators = Vec::new();
+        if matches!(app.input_mode, InputMode::Locked) {
+            indicators.push("🔒".to_string());
+        }
+        if app.dirty {
+            indicators.push("*".to_string());
+        }
+        if !app.index_input.is_empty() {
+            indicators.push(format!("Nomor: {}_", app.index_input));
+        }
+        if app.jump_pending {
+            indicators.push("Lompat ke huruf: _".to_string());
+        }
+        if !app.search_query.is_empty() {
+            indicators.push(format!("⌕ {}", app.search_query));
+        }
+        if let Some(filter) = &app.tag_filter {
+            indicators.push(format!("# {}", filter.label()));
+        }
+        let mut status_text = indicators.join(" ");
+        if !status_text.is_empty() {
+            status_text.push_str(" | ");
+        }
+        status_text.push_str(&format!(
+            "Total Entri: {} | Urutan: {} | Vault: {} | Mode: {}",
+            app.entries.len(),
+            app.sort_mode.label(),
+            app.data_file,
+            match app.input_mode {
+                InputMode::Normal => "Normal",
+                InputMode::EditingAccount => "Input Account",
+                InputMode::EditingUsername => "Input Username",
+                InputMode::EditingUrl => "Input URL",
+                InputMode::EditingNotes => "Input Catatan",
+                InputMode::EditingTags => "Input Tag",
+                InputMode::EditingTotp => "Input TOTP",
+                InputMode::EditingPassword => "Input Password",
+                InputMode::ConfirmDelete => "Konfirmasi Hapus",
+                InputMode::ConfirmDuplicate => "Konfirmasi Duplikat",
+                InputMode::ConfirmQuit => "Konfirmasi Keluar",
+                InputMode::Searching => "Cari",
+                InputMode::Locked => "Terkunci",
+                InputMode::VaultSwitcher => "Pilih Vault",
+                InputMode::VaultPassphrase => "Passphrase Vault",
+                InputMode::DebugBlob => "Debug",
+                InputMode::PasswordHistory => "Riwayat Password",
+                InputMode::ShareToken => "Bagikan Entri",
+                InputMode::NotesView => "Catatan",
+            }
+        ));
+        let inner_width = f.area().width.saturating_sub(6);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(2)
+            .constraints(
+                [
+                    Constraint::Length(3),
+                    Constraint::Length(wrapped_height(&feedback_text, inner_width, 3) + 2),
+                    Constraint::Min(8),
+                    Constraint::Length(4),
+                ]
+                .as_ref(),
+            )
+            .split(f.area());
+
+        let status = Paragraph::new(truncate_to_width(&status_text, inner_width))
+            .style(Style::default().fg(theme.text))
+            .block(theme.block(format!("Password Manager - {}", vault_name(&app.data_file))));
+        f.render_widget(status, chunks[0]);
+
+        let feedback = Paragraph::new(feedback_text)
+            .style(feedback_style)
+            .alignment(Alignment::Center)
+            .block(theme.block("Notifikasi"))
+            .wrap(Wrap { trim: true });
+        f.render_widget(feedback, chunks[1]);
+
+        let main_direction = if chunks[2].width < NARROW_WIDTH {
+            Direction::Vertical
+        } else {
+            Direction::Horizontal
+        };
+        let main_chunks = Layout::default()
+            .direction(main_direction)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(chunks[2]);
+
+        let locked = matches!(app.input_mode, InputMode::Locked);
+        let now = unix_now();
+        let mut items: Vec<ListItem> = app
+            .filtered_indices
+            .iter()
+            .filter(|_| !locked)
+            .filter_map(|&index| app.entries.get(index).map(|entry| (index, entry)))
+            .map(|(index, entry)| {
+                let mut line = match fuzzy_match(&app.search_query, &entry.account) {
+                    Some(positions) if !app.search_query.is_empty() => {
+                        highlighted_line(&entry.account, &positions, &theme)
+                    }
+                    _ => Line::from(entry.account.clone()),
+                };
+                for tag in &entry.tags {
+                    line.spans.push(Span::styled(
+                        format!(" #{}", tag),
+                        Style::default().fg(theme.accent),
+                    ));
+                }
+                if let Some((field, _)) = app
+                    .search_hits
+                    .get(&index)
+                    .filter(|(field, _)| *field != SearchField::Account)
+                {
+                    line.spans.push(Span::styled(
+                        format!(" [{}]", field.label()),
+                        Style::default().fg(theme.muted),
+                    ));
+                }
+                if entry.is_stale(now, app.password_max_age_days) {
+                    line.spans
+                        .insert(0, Span::styled("! ", Style::default().fg(theme.error)));
+                }
+                ListItem::new(line).style(Style::default().fg(theme.text))
+            })
+            .collect();
+        if !locked && items.is_empty() && !app.search_query.is_empty() {
+            items.push(ListItem::new("(tidak ada hasil)").style(Style::default().fg(theme.muted)));
+        }
+
+        let mut list_title = match app.input_mode {
+            InputMode::Searching => format!("Daftar Akun - Cari: {}_", app.search_query),
+            _ if !app.search_query.is_empty() => {
+                format!("Daftar Akun - Filter: {}", app.search_query)
+            }
+            _ => "Daftar Akun".to_string(),
+        };
+        if let Some(filter) = &app.tag_filter {
+            list_title.push_str(&format!(" - Tag: {}", filter.label()));
+        }
+        app.list_area = main_chunks[0];
+        let mut page_state = None;
+        if !locked && !app.filtered_indices.is_empty() {
+            let (page, pages) = app.current_page();
+            list_title.push_str(&format!(" - Halaman {} dari {}", page, pages));
+            // Pages that fit on screen are drawn on their own so each shows exactly its entries.
+            if let Some(start) = app.page_start() {
+                let end = (start + app.page_size()).min(items.len());
+                items = items.drain(start..end).collect();
+                *app.list_state.offset_mut() = start;
+                page_state = Some(
+                    ratatui::widgets::ListState::default()
+                        .with_selected(app.list_state.selected().map(|selected| selected - start)),
+                );
+            }
+        }
+        let list = List::new(items)
+            .block(theme.block(list_title))
+            .highlight_style(
+                Style::default()
+                    .bg(theme.highlight_bg)
+                    .fg(theme.highlight_fg)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        match page_state.as_mut() {
+            Some(state) => f.render_stateful_widget(list, main_chunks[0], state),
+            None => f.render_stateful_widget(list, main_chunks[0], &mut app.list_state),
+        }
+
+        let visible_count = if locked {
+            0
+        } else {
+            app.filtered_indices.len()
+        };
+        if visible_count > app.list_height() {
+            let mut scrollbar_state =
+                ScrollbarState::new(visible_count).position(app.list_state.selected().unwrap_or(0));
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(Some("▲"))
+                .end_symbol(Some("▼"));
+            f.render_stateful_widget(
+                scrollbar,
+                main_chunks[0].inner(Margin {
+                    vertical: 1,
+                    horizontal: 0,
+                }),
+                &mut scrollbar_state,
+            );
+        }
+
+        let detail_block = theme.block("Detail Akun");
+
+        let selected_secrets = app.selected_entry().filter(|_| !locked).map(|entry| {
+            (
+                entry.password.clone(),
+                entry.totp_secret.clone(),
+                entry.notes.clone(),
+            )
+        });
+        let (revealed_password, totp_secret, notes) = match selected_secrets {
+            Some((password, totp_secret, notes)) => (
+                app.revealed.then(|| app.decrypt(&password)),
+                totp_secret.map(|secret| app.decrypt(&secret)),
+                (!notes.is_empty()).then(|| app.decrypt(&notes)),
+            ),
+            None => (None, None, None),
+        };
+        let detail_text = if locked {
+            Text::default()
+        } else if let Some(entry) = app.selected_entry() {
+            let mut lines = vec![
+                Line::from(Span::styled(
+                    format!("Akun: {}", entry.account),
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )),
+                Line::from(format!("Username: {}", display_or_dash(&entry.username))),
+                Line::default(),
+            ];
+            match &revealed_password {
+                Some(Ok(plain)) => {
+                    let label = "Password: ";
+                    let style = Style::default().fg(theme.success);
+                    let inner_width = usize::from(main_chunks[1].width.saturating_sub(2));
+                    if label.width() + plain.width() <= inner_width {
+                        lines.push(Line::from(vec![
+                            Span::raw(label),
+                            Span::styled(plain.as_str(), style),
+                        ]));
+                    } else {
+                        lines.push(Line::from(label));
+                        lines.extend(
+                            split_by_width(plain, inner_width)
+                                .into_iter()
+                                .map(|row| Line::from(Span::styled(row, style))),
+                        );
+                    }
+                }
+                Some(Err(err)) => lines.push(Line::from(Span::styled(
+                    err.as_str(),
+                    Style::default().fg(theme.error),
+                ))),
+                None => lines.push(Line::from(format!(
+                    "Password terenkripsi (disembunyikan): {}",
+                    PASSWORD_MASK
+                ))),
+            }
+            lines.extend([
+                Line::from(format!(
+                    "Tekan {} untuk menampilkan/menyembunyikan password.",
+                    app.keymap.label(Action::Reveal)
+                )),
+                Line::default(),
+                Line::from(format!("URL: {}", display_or_dash(&entry.url))),
+            ]);
+            lines.push(match &notes {
+                Some(Ok(notes)) => {
+                    let first = notes.lines().next().unwrap_or_default();
+                    match notes.lines().count() {
+                        0 | 1 => Line::from(format!("Catatan: {}", first)),
+                        count => Line::from(vec![
+                            Span::raw(format!("Catatan: {} ", first)),
+                            Span::styled(
+                                format!("(+{} baris, tekan 'N')", count - 1),
+                                Style::default().fg(theme.muted),
+                            ),
+                        ]),
+                    }
+                }
+                Some(Err(err)) => {
+                    Line::from(Span::styled(err.as_str(), Style::default().fg(theme.error)))
+                }
+                None => Line::from("Catatan: -"),
+            });
+            lines.extend([
+                Line::from(format!("Tag: {}", display_or_dash(&entry.tags.join(", ")))),
+                Line::from(format!("Terakhir diubah: {}", age_label(entry, now))),
+                match totp_secret {
+                    Some(secret) => totp_line(secret, now, &theme),
+                    None => Line::from("TOTP: -"),
+                },
+            ]);
+            if !entry.history.is_empty() {
+                lines.push(Line::from(format!(
+                    "Riwayat password: {} (tekan 'H')",
+                    entry.history.len()
+                )));
+            }
+            Text::from(lines)
+        } else if !app.entries.is_empty() {
+            Text::default()
+        } else {
+            Text::from(vec![
+                Line::from("Belum ada entri."),
+                Line::from(format!(
+                    "Tekan {} untuk menambahkan akun baru.",
+                    app.keymap.label(Action::Add)
+                )),
+            ])
+        };
+        // Not trimmed so a revealed password keeps its leading spaces when it wraps.
+        let detail = Paragraph::new(detail_text)
+            .block(detail_block)
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false });
+        f.render_widget(detail, main_chunks[1]);
+
+        let keymap = &app.keymap;
+        let normal_instructions = [
+            format!(
+                "[Navigasi] {}, {}, PgUp/PgDn, Home/End",
+                keymap.label(Action::Previous),
+                keymap.label(Action::Next)
+            ),
+            format!("[Tambah] {}", keymap.label(Action::Add)),
+            format!("[Tampilkan Password] {}", keymap.label(Action::Reveal)),
+            "[Salin Password] 'c'".to_string(),
+            "[Salin Username] 'C'".to_string(),
+            "[Buka URL] 'o'".to_string(),
+            format!("[Cari] {}", keymap.label(Action::Search)),
+            "[Urutkan] 's'".to_string(),
+            "[Pindahkan Entri] Shift+Atas/Bawah".to_string(),
+            "[Filter Tag] 'g' ('t' dipakai untuk Tema)".to_string(),
+            "[Tema] 't'".to_string(),
+            "[Ganti Vault] 'V'".to_string(),
+            "[Edit] 'e'".to_string(),
+            "[Riwayat Password] 'H'".to_string(),
+            "[Bagikan sebagai Token] 'x'".to_string(),
+            "[Lihat Catatan] 'N'".to_string(),
+            "[Lompat ke Nomor] 0-9 lalu Enter ('c' untuk menyalin)".to_string(),
+            "[Lompat ke Huruf] ' lalu huruf".to_string(),
+            format!("[Hapus] {}", keymap.label(Action::Delete)),
+            "[Simpan] Ctrl+S".to_string(),
+            format!("[Keluar] {}", keymap.label(Action::Quit)),
+        ];
+        let instruction_lines = match app.input_mode {
+            InputMode::Normal => normal_instructions.iter().map(String::as_str).collect(),
+            InputMode::EditingAccount => vec![
+                "Masukkan nama akun.",
+                "Enter untuk lanjut ke input username.",
+                "Esc untuk membatalkan.",
+            ],
+            InputMode::EditingUsername => vec![
+                "Masukkan username login (boleh kosong).",
+                "Enter untuk lanjut ke input password.",
+                "Esc untuk membatalkan.",
+            ],
+            InputMode::EditingPassword => vec![
+                "Masukkan password.",
+                "Ctrl+G untuk membuat password acak.",
+                "Ctrl+P untuk membuat passphrase yang mudah diingat.",
+                "Ctrl+H untuk menampilkan/menyembunyikan ketikan.",
+                "Enter untuk lanjut ke input URL.",
+                "Esc untuk membatalkan.",
+            ],
+            InputMode::EditingUrl => vec![
+                "Masukkan URL login (boleh kosong).",
+                "Enter untuk lanjut ke input catatan.",
+                "Esc untuk membatalkan.",
+            ],
+            InputMode::EditingNotes => vec![
+                "Masukkan catatan (boleh kosong, Enter untuk baris baru).",
+                "Ctrl+S untuk menyimpan catatan dan lanjut ke input tag.",
+                "Esc untuk membatalkan.",
+            ],
+            InputMode::EditingTags => vec![
+                "Masukkan tag dipisahkan koma, mis. bank, email (boleh kosong).",
+                "Enter untuk lanjut ke input secret TOTP.",
+                "Esc untuk membatalkan.",
+            ],
+            InputMode::EditingTotp => vec![
+                "Masukkan secret TOTP base32 (boleh kosong).",
+                "Enter untuk menyimpan entri.",
+                "Esc untuk membatalkan.",
+            ],
+            InputMode::Searching => vec![
+                "Ketik untuk mencari akun.",
+                "Panah Atas/Bawah untuk navigasi.",
+                "Enter untuk selesai, Esc untuk menghapus filter.",
+            ],
+            InputMode::Locked => vec![
+                "Vault terkunci karena tidak ada aktivitas.",
+                "Masukkan passphrase lalu tekan Enter untuk membuka.",
+            ],
+            InputMode::VaultSwitcher => vec![
+                "Panah Atas/Bawah untuk memilih vault.",
+                "Enter untuk membuka, Esc untuk membatalkan.",
+            ],
+            InputMode::VaultPassphrase => vec![
+                "Masukkan passphrase vault yang dipilih.",
+                "Enter untuk membuka, Esc untuk kembali.",
+            ],
+            InputMode::DebugBlob => vec!["Esc atau Enter untuk menutup."],
+            InputMode::ShareToken if app.share_token.is_some() => {
+                vec!["Esc atau Enter untuk menutup."]
+            }
+            InputMode::ShareToken => vec![
+                "Masukkan passphrase sekali pakai untuk token.",
+                "Enter untuk membuat token, Esc untuk membatalkan.",
+            ],
+            InputMode::NotesView => vec![
+                "Panah Atas/Bawah, PageUp/PageDown untuk menggulir, Home ke awal.",
+                "Esc, 'q' atau 'N' untuk menutup.",
+            ],
+            InputMode::PasswordHistory => vec![
+                "Panah Atas/Bawah untuk memilih password lama.",
+                "'c' untuk menyalin, 'r' untuk memulihkan, Esc untuk menutup.",
+            ],
+            InputMode::ConfirmQuit => vec![
+                "Ada perubahan yang belum disimpan.",
+                "'s' simpan lalu keluar, 'b' buang perubahan, Esc batal.",
+            ],
+            InputMode::ConfirmDelete => vec![
+                "Tekan 'y' untuk menghapus entri.",
+                "Tekan 'n' atau Esc untuk membatalkan.",
+            ],
+            InputMode::ConfirmDuplicate => vec![
+                "Tekan 'y' untuk memperbarui entri yang sudah ada.",
+                "Tekan 'n' atau Esc untuk mengganti nama akun.",
+            ],
+        };
+
+        let instruction_text = Text::from(Line::from(instruction_lines.join("  ")));
+
+        let instruction = Paragraph::new(instruction_text)
+            .block(theme.block("Instruksi"))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: true });
+        f.render_widget(instruction, chunks[3]);
+
+        match app.input_mode {
+            InputMode::EditingNotes => {
+                let area = centered_rect(70, 50, f.area());
+                f.render_widget(Clear, area);
+
+                let title = match app.editing_index {
+                    Some(_) => "Edit Entri - Catatan",
+                    None => "Entri Baru - Catatan",
+                };
+                let (row, column) = cursor_row_column(&app.notes_input, app.cursor_pos);
+                let inner_height = area.height.saturating_sub(3) as usize;
+                let scroll = row.saturating_sub(inner_height.saturating_sub(1));
+                let mut popup_lines: Vec<Line> = app
+                    .notes_input
+                    .split('\n')
+                    .skip(scroll)
+                    .take(inner_height)
+                    .map(Line::from)
+                    .collect();
+                popup_lines.resize(inner_height, Line::default());
+                popup_lines.push(Line::from(Span::styled(
+                    format!(
+                        "Baris {}, kolom {} | Karakter: {}",
+                        row + 1,
+                        column + 1,
+                        app.notes_input.chars().count()
+                    ),
+                    Style::default().fg(theme.muted),
+                )));
+                let notes = Paragraph::new(Text::from(popup_lines)).block(theme.block(title));
+                f.render_widget(notes, area);
+
+                let current_line = app.notes_input.split('\n').nth(row).unwrap_or_default();
+                let before_cursor = &current_line[..char_to_byte_index(current_line, column)];
+                let cursor_x = area.x + 1 + Line::from(before_cursor).width() as u16;
+                f.set_cursor_position(Position::new(
+                    cursor_x.min(area.right().saturating_sub(2)),
+                    area.y + 1 + (row - scroll) as u16,
+                ));
+            }
+            InputMode::EditingAccount
+            | InputMode::EditingUsername
+            | InputMode::EditingPassword
+            | InputMode::EditingUrl
+            | InputMode::EditingTags
+            | InputMode::EditingTotp => {
+                let height = match app.input_mode {
+                    InputMode::EditingPassword => 30,
+                    _ => 25,
+                };
+                let area = centered_rect(60, height, f.area());
+                f.render_widget(Clear, area);
+
+                let (field, input_text) = match app.input_mode {
+                    InputMode::EditingAccount => ("Account", &app.account_input),
+                    InputMode::EditingUsername => ("Username", &app.username_input),
+                    InputMode::EditingUrl => ("URL", &app.url_input),
+                    InputMode::EditingTags => ("Tag", &app.tags_input),
+                    InputMode::EditingTotp => ("Secret TOTP", &app.totp_input),
+                    _ => ("Password", &app.password_input),
+                };
+                let title = match app.editing_index {
+                    Some(_) => format!("Edit Entri - {}", field),
+                    None => format!("Entri Baru - {}", field),
+                };
+                let display_text = match app.input_mode {
+                    InputMode::EditingPassword if !app.show_password => {
+                        Zeroizing::new("*".repeat(input_text.chars().count()))
+                    }
+                    _ => Zeroizing::new(input_text.clone()),
+                };
+                let mut popup_lines = vec![
+                    Line::from(display_text.as_str()),
+                    Line::from(Span::styled(
+                        format!("Karakter: {}", input_text.chars().count()),
+                        Style::default().fg(theme.muted),
+                    )),
+                ];
+                if let Some(error) = &app.input_error {
+                    popup_lines.push(Line::from(Span::styled(
+                        error.as_str(),
+                        Style::default().fg(theme.error),
+                    )));
+                }
+                if let InputMode::EditingPassword = app.input_mode {
+                    if app.editing_index.is_some() && app.password_input.is_empty() {
+                        popup_lines.push(Line::from(Span::styled(
+                            "Kosongkan untuk mempertahankan password lama",
+                            Style::default().fg(theme.muted),
+                        )));
+                    } else {
+                        popup_lines.push(strength_line(&app.password_input, &theme));
+                        popup_lines.push(strength_checks_line(&app.password_input, &theme));
+                    }
+                }
+                let popup_text = Text::from(popup_lines);
+                let input = Paragraph::new(popup_text)
+                    .block(theme.block(title))
+                    .alignment(Alignment::Center);
+                f.render_widget(input, area);
+
+                let inner_width = area.width.saturating_sub(2);
+                let text_width = Line::from(display_text.as_str()).width() as u16;
+                let before_cursor =
+                    &display_text[..char_to_byte_index(&display_text, app.cursor_pos)];
+                let cursor_x = area.x
+                    + 1
+                    + (inner_width / 2).saturating_sub(text_width / 2)
+                    + Line::from(before_cursor).width() as u16;
+                f.set_cursor_position(Position::new(
+                    cursor_x.min(area.right().saturating_sub(2)),
+                    area.y + 1,
+                ));
+            }
+            InputMode::Locked => {
+                let area = centered_rect(60, 20, f.area());
+                f.render_widget(Clear, area);
+
+                let popup_text = Text::from(vec![
+                    Line::from("*".repeat(app.passphrase_input.chars().count())),
+                    Line::from(Span::styled(
+                        "Masukkan passphrase master",
+                        Style::default().fg(theme.muted),
+                    )),
+                ]);
+                let unlock = Paragraph::new(popup_text)
+                    .block(theme.block("Vault Terkunci"))
+                    .alignment(Alignment::Center);
+                f.render_widget(unlock, area);
+            }
+            InputMode::VaultSwitcher => {
+                let area = centered_rect(60, 40, f.area());
+                f.render_widget(Clear, area);
+
+                let items: Vec<ListItem> = app
+                    .vault_choices
+                    .iter()
+                    .map(|path| {
+                        let mut label = vault_name(path);
+                        if *path == app.data_file {
+                            label.push_str(" (aktif)");
+                        }
+                        ListItem::new(label)
+                    })
+                    .collect();
+                let vaults = List::new(items)
+                    .block(theme.block("Pilih Vault"))
+                    .highlight_style(
+                        Style::default()
+                            .fg(theme.accent)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .highlight_symbol(">> ");
+                f.render_stateful_widget(vaults, area, &mut app.vault_list_state);
+            }
+            InputMode::VaultPassphrase => {
+                let area = centered_rect(60, 20, f.area());
+                f.render_widget(Clear, area);
+
+                let name = app
+                    .vault_list_state
+                    .selected()
+                    .and_then(|position| app.vault_choices.get(position))
+                    .map(|path| vault_name(path))
+                    .unwrap_or_default();
+                let popup_text = Text::from(vec![
+                    Line::from("*".repeat(app.passphrase_input.chars().count())),
+                    Line::from(Span::styled(
+                        "Masukkan passphrase vault",
+                        Style::default().fg(theme.muted),
+                    )),
+                ]);
+                let prompt = Paragraph::new(popup_text)
+                    .block(theme.block(format!("Buka Vault - {}", name)))
+                    .alignment(Alignment::Center);
+                f.render_widget(prompt, area);
+            }
+            InputMode::ShareToken => {
+                let area = centered_rect(70, 30, f.area());
+                f.render_widget(Clear, area);
+
+                let account = app
+                    .selected_entry()
+                    .map(|entry| entry.account.clone())
+                    .unwrap_or_default();
+                let lines = match &app.share_token {
+                    Some(token) => vec![
+                        Line::from(token.as_str()),
+                        Line::default(),
+                        Line::from(Span::styled(
+                            "Impor dengan: password_manager import-token <token>",
+                            Style::default().fg(theme.muted),
+                        )),
+                    ],
+                    None => vec![
+                        Line::from("*".repeat(app.passphrase_input.chars().count())),
+                        Line::from(Span::styled(
+                            "Passphrase sekali pakai, terpisah dari passphrase vault",
+                            Style::default().fg(theme.muted),
+                        )),
+                    ],
+                };
+                let popup = Paragraph::new(Text::from(lines))
+                    .block(theme.block(format!("Bagikan Entri - {}", account)))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: false });
+                f.render_widget(popup, area);
+            }
+            InputMode::NotesView => {
+                let area = centered_rect(70, 60, f.area());
+                f.render_widget(Clear, area);
+
+                let account = app
+                    .selected_entry()
+                    .map(|entry| entry.account.clone())
+                    .unwrap_or_default();
+                let text = match &notes {
+                    Some(Ok(notes)) => Text::from(notes.as_str()),
+                    Some(Err(err)) => {
+                        Text::from(Span::styled(err.as_str(), Style::default().fg(theme.error)))
+                    }
+                    None => Text::from("-"),
+                };
+                let popup = Paragraph::new(text)
+                    .block(theme.block(format!("Catatan - {}", account)))
+                    .wrap(Wrap { trim: false })
+                    .scroll((app.notes_scroll, 0));
+                f.render_widget(popup, area);
+            }
+            InputMode::PasswordHistory => {
+                let area = centered_rect(60, 40, f.area());
+                f.render_widget(Clear, area);
+
+                let now = unix_now();
+                let (account, items) = match app.selected_entry() {
+                    Some(entry) => (
+                        entry.account.clone(),
+                        entry
+                            .history
+                            .iter()
+                            .enumerate()
+                            .map(|(position, old)| {
+                                ListItem::new(format!(
+                                    "{}. {} ({})",
+                                    position + 1,
+                                    PASSWORD_MASK,
+                                    replaced_label(old, now)
+                                ))
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+                    None => (String::new(), Vec::new()),
+                };
+                let history = List::new(items)
+                    .block(theme.block(format!("Riwayat Password - {}", account)))
+                    .highlight_style(
+                        Style::default()
+                            .fg(theme.accent)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .highlight_symbol(">> ");
+                f.render_stateful_widget(history, area, &mut app.history_list_state);
+            }
+            InputMode::DebugBlob => {
+                let area = centered_rect(80, 50, f.area());
+                f.render_widget(Clear, area);
+
+                let mut lines = Vec::new();
+                if let Some(entry) = app.selected_entry() {
+                    lines.push(Line::from(format!("Akun: {}", entry.account)));
+                    let fields = [
+                        ("Password", Some(&entry.password)),
+                        ("TOTP", entry.totp_secret.as_ref()),
+                    ];
+                    for (label, value) in fields {
+                        let Some(value) = value else {
+                            continue;
+                        };
+                        lines.push(Line::default());
+                        lines.push(Line::from(format!("{}: {}", label, value)));
+                        lines.push(Line::from(Span::styled(
+                            blob_summary(value),
+                            Style::default().fg(theme.muted),
+                        )));
+                    }
+                }
+                let popup = Paragraph::new(Text::from(lines))
+                    .block(theme.block("Debug - Data Terenkripsi"))
+                    .wrap(Wrap { trim: false });
+                f.render_widget(popup, area);
+            }
+            InputMode::ConfirmQuit => {
+                let area = centered_rect(60, 20, f.area());
+                f.render_widget(Clear, area);
+
+                let popup_text = Text::from(vec![
+                    Line::from("Simpan perubahan sebelum keluar?"),
+                    Line::from(Span::styled(
+                        "s = simpan, b = buang, Esc = batal",
+                        Style::default().fg(theme.muted),
+                    )),
+                ]);
+                let confirm = Paragraph::new(popup_text)
+                    .block(theme.block("Konfirmasi Keluar"))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true });
+                f.render_widget(confirm, area);
+            }
+            InputMode::ConfirmDelete => {
+                let area = centered_rect(60, 20, f.area());
+                f.render_widget(Clear, area);
+
+                let account = app
+                    .selected_entry()
+                    .map(|entry| entry.account.as_str())
+                    .unwrap_or_default();
+                let popup_text = Text::from(vec![
+                    Line::from(format!("Hapus entri '{}'?", account)),
+                    Line::from(Span::styled(
+                        "y = hapus, n/Esc = batal",
+                        Style::default().fg(theme.muted),
+                    )),
+                ]);
+                let confirm = Paragraph::new(popup_text)
+                    .block(theme.block("Konfirmasi Hapus"))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true });
+                f.render_widget(confirm, area);
+            }
+            InputMode::ConfirmDuplicate => {
+                let area = centered_rect(60, 20, f.area());
+                f.render_widget(Clear, area);
+
+                let account = app
+                    .duplicate_of
+                    .and_then(|index| app.entries.get(index))
+                    .map(|entry| entry.account.as_str())
+                    .unwrap_or_default();
+                let popup_text = Text::from(vec![
+                    Line::from(format!(
+                        "Akun '{}' sudah ada. Perbarui entri tersebut?",
+                        account
+                    )),
+                    Line::from(Span::styled(
+                        "y = perbarui, n/Esc = ganti nama",
+                        Style::default().fg(theme.muted),
+                    )),
+                ]);
+                let confirm = Paragraph::new(popup_text)
+                    .block(theme.block("Konfirmasi Duplikat"))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true });
+                f.render_widget(confirm, area);
+            }
+            _ => {}
+        }
+    })?;
+    Ok(())
+}
+
+fn vault_dir() -> Option<PathBuf> {
+    ProjectDirs::from("com", "MamangRust", "password-manager")
+        .map(|dirs| dirs.config_dir().to_path_buf())
+}
+
+fn vault_name(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn list_vault_files(current: &str) -> Vec<String> {
+    let mut files: Vec<String> = vault_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    if !files.iter().any(|path| path == current) {
+        files.push(current.to_string());
+    }
+    files.sort();
+    files
+}
+
+fn resolve_vault_path(flag: Option<String>) -> Result<String, String> {
+    if let Some(path) = flag.filter(|path| !path.trim().is_empty()) {
+        return Ok(path);
+    }
+    if let Some(path) = env::var("PASSWORD_MANAGER_FILE")
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+    {
+        return Ok(path);
+    }
+    let dir =
+        vault_dir().ok_or_else(|| "Direktori konfigurasi pengguna tidak ditemukan.".to_string())?;
+    Ok(dir.join("passwords.txt").to_string_lossy().into_owned())
+}
+
+#[derive(Parser)]
+#[command(
+    name = "password-manager",
+    about = "Password manager terminal berbasis ratatui"
+)]
+struct Cli {
+    /// Lokasi file vault (default: PASSWORD_MANAGER_FILE atau direktori konfigurasi).
+    #[arg(long, visible_alias = "file", global = true)]
+    vault: Option<String>,
+    /// Tampilkan dari mana PASSWORD_MANAGER_KEY diambil (environment atau .env).
+    #[arg(long, global = true)]
+    verbose: bool,
+    /// Aktifkan Ctrl+D di TUI untuk melihat data terenkripsi mentah entri yang dipilih.
+    #[arg(long)]
+    debug: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Cetak password akun yang sudah didekripsi ke stdout.
+    Get { account: String },
+    /// Cetak semua nama akun.
+    List,
+    /// Ekspor vault ke file JSON tanpa mendekripsi password.
+    ExportJson { path: String },
+    /// Gabungkan entri dari file JSON hasil ekspor, lewati akun yang sudah ada.
+    ImportJson { path: String },
+    /// Impor login dari file CSV ekspor browser (name,url,username,password).
+    ImportCsv { path: String },
+    /// Tambahkan entri dari token yang dibuat dengan tombol 'x' di TUI.
+    ImportToken { token: String },
+    /// Ganti passphrase master dan enkripsi ulang seluruh vault.
+    ChangeKey,
+    /// Tampilkan daftar backup vault, atau pulihkan backup dengan nomor tertentu.
+    Restore { number: Option<usize> },
+    /// Laporkan password lemah atau yang dipakai ulang oleh beberapa akun.
+    Audit {
+        /// Skor minimum (0-8) agar password tidak dianggap lemah.
+        #[arg(long, default_value_t = 4)]
+        min_score: u32,
+        /// Telusuri entri bermasalah satu per satu dan buat ulang passwordnya.
+        #[arg(long)]
+        fix: bool,
+    },
+}
+
+/// Version 2 encrypts each entry's notes; version 1 files with plaintext notes still import.
+const JSON_SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct JsonExport {
+    schema_version: u32,
+    format_version: u32,
+    salt: String,
+    #[serde(default)]
+    cipher: CipherAlgorithm,
+    sentinel: String,
+    entries: Vec<Entry>,
+}
+
+fn open_vault(data_file: &str) -> Result<Vault, Box<dyn Error>> {
+    let passphrase = read_passphrase()?;
+    match load_vault(data_file, &passphrase) {
+        Ok(vault) => {
+            for corrupt in &vault.corrupt {
+                eprintln!("Baris {} dilewati: {}.", corrupt.line, corrupt.reason);
+            }
+            if let Some(warning) = load_warning(&vault.corrupt, vault.tampered) {
+                eprintln!(
+                    "{} Salinan asli disimpan di {}.",
+                    warning,
+                    corrupt_backup_path(data_file)
+                );
+            }
+            Ok(vault)
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_get(data_file: &str, account: &str) -> Result<(), Box<dyn Error>> {
+    let Vault {
+        entries, cipher, ..
+    } = open_vault(data_file)?;
+    let query = normalize_account(account);
+    let Some(entry) = entries
+        .iter()
+        .find(|entry| normalize_account(&entry.account) == query)
+    else {
+        eprintln!("Akun '{}' tidak ditemukan.", account);
+        std::process::exit(1);
+    };
+    let plain = decrypt_password(&cipher, &entry.password)?;
+    println!("{}", *plain);
+    Ok(())
+}
+
+fn run_list(data_file: &str) -> Result<(), Box<dyn Error>> {
+    let Vault { entries, .. } = open_vault(data_file)?;
+    for entry in &entries {
+        println!("{}", entry.account);
+    }
+    Ok(())
+}
+
+fn run_export_json(data_file: &str, path: &str) -> Result<(), Box<dyn Error>> {
+    let Vault {
+        entries, cipher, ..
+    } = open_vault(data_file)?;
+    let salt = read_salt(&salt_path(data_file))?;
+    let export = JsonExport {
+        schema_version: JSON_SCHEMA_VERSION,
+        format_version: FORMAT_VERSION,
+        salt: general_purpose::STANDARD.encode(salt),
+        cipher: cipher.algorithm(),
+        sentinel: encrypt_password(&cipher, VERIFY_SENTINEL)?,
+        entries,
+    };
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // The export carries the salt and every ciphertext, so it is as private as the vault.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(serde_json::to_string_pretty(&export)?.as_bytes())?;
+    file.sync_all()?;
+    println!("{} entri diekspor ke {}.", export.entries.len(), path);
+    Ok(())
+}
+
+fn run_import_json(data_file: &str, path: &str) -> Result<(), Box<dyn Error>> {
+    let _lock = lock_vault(data_file)?;
+    let Vault {
+        mut entries,
+        cipher,
+        ..
+    } = open_vault(data_file)?;
+    let export: JsonExport = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| format!("File JSON tidak valid: {}", e))?;
+    if !(1..=JSON_SCHEMA_VERSION).contains(&export.schema_version) {
+        return Err(format!("Versi skema JSON {} tidak didukung.", export.schema_version).into());
+    }
+    let import_salt = general_purpose::STANDARD
+        .decode(&export.salt)
+        .map_err(|_| "Salt pada file JSON tidak valid.".to_string())?;
+    // The sentinel decides which key the export was made with: the salt alone cannot, because
+    // change-key keeps the salt while replacing the passphrase.
+    let same_key =
+        export.cipher == cipher.algorithm() && verify_sentinel(&cipher, &export.sentinel).is_ok();
+    let import_cipher = if same_key {
+        cipher.clone()
+    } else {
+        let candidate = VaultCipher::with_algorithm(
+            derive_key(&read_passphrase()?, &import_salt)?,
+            export.cipher,
+        );
+        if verify_sentinel(&candidate, &export.sentinel).is_ok() {
+            candidate
+        } else {
+            let passphrase = prompt_passphrase("Passphrase file ekspor: ")?;
+            let candidate =
+                VaultCipher::with_algorithm(derive_key(&passphrase, &import_salt)?, export.cipher);
+            verify_sentinel(&candidate, &export.sentinel)
+                .map_err(|_| "Passphrase tidak cocok dengan file ekspor.".to_string())?;
+            candidate
+        }
+    };
+
+    let mut imported = 0;
+    let mut skipped = 0;
+    let mut progress = Progress::new(export.entries.len());
+    for mut entry in export.entries {
+        progress.step();
+        entry.account = normalize_account(&entry.account);
+        if entries
+            .iter()
+            .any(|existing| normalize_account(&existing.account) == entry.account)
+        {
+            skipped += 1;
+            continue;
+        }
+        if export.schema_version < 2 {
+            entry.notes = encrypt_notes(&import_cipher, &entry.notes)?;
+        }
+        if !same_key {
+            reencrypt_entries(std::slice::from_mut(&mut entry), &import_cipher, &cipher)?;
+        }
+        entries.push(entry);
+        imported += 1;
+    }
+    drop(progress);
+    if imported > 0 {
+        save_entries(data_file, &entries, &cipher)?;
+    }
+    println!(
+        "{} entri diimpor, {} dilewati karena akun sudah ada.",
+        imported, skipped
+    );
+    Ok(())
+}
+
+fn run_import_csv(data_file: &str, path: &str) -> Result<(), Box<dyn Error>> {
+    let _lock = lock_vault(data_file)?;
+    let Vault {
+        mut entries,
+        cipher,
+        ..
+    } = open_vault(data_file)?;
+    let content = fs::read_to_string(path)?;
+    let mut records = parse_csv_records(content.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("File CSV tidak valid: {}", e))?
+        .into_iter()
+        .map(|(_, record)| record);
+    let header = records.next().unwrap_or_default();
+    let records: Vec<Vec<String>> = records.collect();
+    let column = |name: &str, fallback: usize| {
+        header
+            .iter()
+            .position(|field| field.trim().eq_ignore_ascii_case(name))
+            .unwrap_or(fallback)
+    };
+    let (name_col, url_col, username_col, password_col, note_col) = (
+        column("name", 0),
+        column("url", 1),
+        column("username", 2),
+        column("password", 3),
+        column("note", usize::MAX),
+    );
+    let field = |record: &[String], index: usize| {
+        record
+            .get(index)
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let now = unix_now();
+    let policy = duplicate_policy();
+    let mut ask_duplicates = policy == DuplicatePolicy::Prompt;
+    let (mut imported, mut updated, mut skipped, mut duplicates) = (0, 0, 0, 0);
+    let mut progress = Progress::new(records.len());
+    for record in records {
+        progress.step();
+        let password = Zeroizing::new(field(&record, password_col));
+        let url = field(&record, url_col);
+        let mut account = normalize_account(&field(&record, name_col));
+        if account.is_empty() {
+            account = url.clone();
+        }
+        if password.is_empty() || account.is_empty() {
+            skipped += 1;
+            continue;
+        }
+        if let Some(existing) = find_account(&entries, &account, None) {
+            let prompt = format!(
+                "Akun '{}' sudah ada. Perbarui entri tersebut? [y/N/q] ",
+                entries[existing].account
+            );
+            let update = ask_duplicates
+                && match confirm(&prompt)? {
+                    Some(answer) => answer,
+                    // 'q' or end of input: every remaining duplicate is skipped without asking.
+                    None => {
+                        ask_duplicates = false;
+                        false
+                    }
+                };
+            if !update {
+                duplicates += 1;
+                continue;
+            }
+            let entry = &mut entries[existing];
+            let changed = decrypt_password(&cipher, &entry.password)
+                .map_or(true, |plain| *plain != *password);
+            if changed {
+                let previous =
+                    std::mem::replace(&mut entry.password, encrypt_password(&cipher, &password)?);
+                entry.push_history(previous, now);
+            }
+            entry.username = field(&record, username_col);
+            entry.url = url;
+            entry.notes = encrypt_notes(&cipher, &field(&record, note_col))?;
+            entry.updated_at = now;
+            updated += 1;
+            continue;
+        }
+        entries.push(Entry {
+            account,
+            username: field(&record, username_col),
+            password: encrypt_password(&cipher, &password)?,
+            url,
+            notes: encrypt_notes(&cipher, &field(&record, note_col))?,
+            created_at: now,
+            updated_at: now,
+            totp_secret: None,
+            tags: Vec::new(),
+            history: Vec::new(),
+        });
+        imported += 1;
+    }
+    drop(progress);
+    if imported + updated > 0 {
+        save_entries(data_file, &entries, &cipher)?;
+    }
+    println!(
+        "{} entri diimpor, {} diperbarui, {} baris dilewati karena password kosong, {} dilewati karena akun sudah ada.",
+        imported, updated, skipped, duplicates
+    );
+    Ok(())
+}
+
+fn prompt_passphrase(prompt: &str) -> Result<Zeroizing<String>, String> {
+    rpassword::prompt_password(prompt)
+        .map(Zeroizing::new)
+        .map_err(|e| format!("Gagal membaca passphrase: {}", e))
+}
+
+fn run_import_token(data_file: &str, token: &str) -> Result<(), Box<dyn Error>> {
+    let _lock = lock_vault(data_file)?;
+    let Vault {
+        mut entries,
+        cipher,
+        ..
+    } = open_vault(data_file)?;
+    let passphrase = prompt_passphrase("Passphrase token: ")?;
+    let entry = import_token(token, &passphrase, &cipher)?;
+    if entries
+        .iter()
+        .any(|existing| normalize_account(&existing.account) == entry.account)
+    {
+        return Err(format!("Akun '{}' sudah ada di vault.", entry.account).into());
+    }
+    println!("Entri '{}' diimpor dari token.", entry.account);
+    entries.push(entry);
+    save_entries(data_file, &entries, &cipher)?;
+    Ok(())
+}
+
+fn run_change_key(data_file: &str) -> Result<(), Box<dyn Error>> {
+    let _lock = lock_vault(data_file)?;
+    let current = prompt_passphrase("Passphrase saat ini: ")?;
+    let Vault {
+        mut entries,
+        cipher: old_cipher,
+        corrupt,
+        tampered,
+        ..
+    } = load_vault(data_file, &current)?;
+    if let Some(warning) = load_warning(&corrupt, tampered) {
+        return Err(format!("{} Perbaiki vault sebelum mengganti passphrase.", warning).into());
+    }
+    let new_passphrase = prompt_passphrase("Passphrase baru: ")?;
+    if new_passphrase.trim().is_empty() {
+        return Err("Passphrase baru tidak boleh kosong.".into());
+    }
+    let confirmation = prompt_passphrase("Ulangi passphrase baru: ")?;
+    if !bool::from(confirmation.as_bytes().ct_eq(new_passphrase.as_bytes())) {
+        return Err("Konfirmasi passphrase baru tidak cocok.".into());
+    }
+    // The salt is kept so the vault file is the only thing rewritten; the atomic rename in
+    // save_entries then guarantees the vault is either fully old-key or fully new-key.
+    let salt = read_salt(&salt_path(data_file))?;
+    let algorithm = requested_algorithm().unwrap_or(old_cipher.algorithm());
+    let new_cipher = VaultCipher::with_algorithm(derive_key(&new_passphrase, &salt)?, algorithm);
+    let mut progress = Progress::new(entries.len());
+    reencrypt_entries_with(&mut entries, &old_cipher, &new_cipher, || progress.step())?;
+    drop(progress);
+    save_entries(data_file, &entries, &new_cipher)?;
+    println!(
+        "Passphrase diganti, {} entri dienkripsi ulang dengan {}. Perbarui PASSWORD_MANAGER_KEY dengan passphrase baru.",
+        entries.len(),
+        new_cipher.algorithm().label()
+    );
+    Ok(())
+}
+
+fn run_restore(data_file: &str, number: Option<usize>) -> Result<(), Box<dyn Error>> {
+    let backups = list_backups(data_file);
+    let Some(number) = number else {
+        if backups.is_empty() {
+            println!("Belum ada backup untuk {}.", data_file);
+        }
+        let now = unix_now();
+        for (position, (timestamp, path)) in backups.iter().enumerate() {
+            let minutes = now.saturating_sub(*timestamp) / 60;
+            println!(
+                "{}. {} ({} menit lalu)",
+                position + 1,
+                path.display(),
+                minutes
+            );
+        }
+        return Ok(());
+    };
+    let (_, backup) = number
+        .checked_sub(1)
+        .and_then(|index| backups.get(index))
+        .ok_or_else(|| format!("Backup nomor {} tidak ditemukan.", number))?;
+    let backup = backup.to_string_lossy().into_owned();
+    let _lock = lock_vault(data_file)?;
+    // The backup is checked against the current key on its own, so a broken vault can still be
+    // restored, but a backup from before `change-key` never replaces a vault that still opens.
+    let passphrase = read_passphrase()?;
+    let cipher = VaultCipher::with_algorithm(
+        derive_key(&passphrase, &read_salt(&salt_path(data_file))?)?,
+        vault_algorithm(&backup)?.unwrap_or_default(),
+    );
+    let loaded = load_entries(&backup, &cipher).map_err(|e| match e.kind() {
+        io::ErrorKind::PermissionDenied => {
+            "Backup dibuat dengan passphrase lain dan tidak dapat dipulihkan.".to_string()
+        }
+        _ => format!("Backup tidak dapat dibaca: {}", e),
+    })?;
+    if let Some(warning) = load_warning(&loaded.corrupt, loaded.tampered) {
+        return Err(format!("Backup tidak dipulihkan: {}", warning).into());
+    }
+    replace_file(data_file, |tmp_path| {
+        fs::copy(&backup, tmp_path).map(|_| ())
+    })?;
+    println!(
+        "Vault dipulihkan dari {} ({} entri).",
+        backup,
+        loaded.entries.len()
+    );
+    Ok(())
+}
+
+fn audit_issues(
+    plaintexts: &[Option<Zeroizing<String>>],
+    entries: &[Entry],
+    index: usize,
+    min_score: u32,
+) -> Vec<String> {
+    let Some(password) = &plaintexts[index] else {
+        return vec!["password tidak dapat didekripsi".to_string()];
+    };
+    let mut issues = Vec::new();
+    let score = password_score(password);
+    if score < min_score {
+        issues.push(format!(
+            "skor {}/{} ({})",
+            score,
+            MAX_PASSWORD_SCORE,
+            password_strength(password).label()
+        ));
+    }
+    let reused: Vec<&str> = plaintexts
+        .iter()
+        .enumerate()
+        .filter(|(other, plain)| *other != index && plain.as_ref() == Some(password))
+        .map(|(other, _)| entries[other].account.as_str())
+        .collect();
+    if !reused.is_empty() {
+        issues.push(format!("dipakai juga oleh {}", reused.join(", ")));
+    }
+    issues
+}
+
+/// A `Memproses n/total` line on stderr for long CLI loops. It is redrawn at most every
+/// 100 ms and skipped when stderr is not a terminal, so piped output stays clean.
+struct Progress {
+    done: usize,
+    total: usize,
+    enabled: bool,
+    last_draw: Option<Instant>,
+}
+
+impl Progress {
+    fn new(total: usize) -> Progress {
+        Progress {
+            done: 0,
+            total,
+            enabled: total > 0 && io::stderr().is_terminal(),
+            last_draw: None,
+        }
+    }
+
+    fn step(&mut self) {
+        self.done += 1;
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        let recent = self
+            .last_draw
+            .is_some_and(|at| now.duration_since(at) < Duration::from_millis(100));
+        if recent && self.done < self.total {
+            return;
+        }
+        self.last_draw = Some(now);
+        eprint!("\rMemproses {}/{}", self.done, self.total);
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.last_draw.is_some() {
+            eprintln!();
+        }
+    }
+}
+
+fn confirm(prompt: &str) -> Result<Option<bool>, Box<dyn Error>> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        return Ok(None);
+    }
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "y" => Some(true),
+        "q" => None,
+        _ => Some(false),
+    })
+}
+
+fn run_audit(data_file: &str, min_score: u32, fix: bool) -> Result<(), Box<dyn Error>> {
+    let _lock = if fix {
+        Some(lock_vault(data_file)?)
+    } else {
+        None
+    };
+    let Vault {
+        mut entries,
+        cipher,
+        ..
+    } = open_vault(data_file)?;
+    let mut progress = Progress::new(entries.len());
+    let mut plaintexts: Vec<Option<Zeroizing<String>>> = entries
+        .iter()
+        .map(|entry| {
+            progress.step();
+            decrypt_password(&cipher, &entry.password).ok()
+        })
+        .collect();
+    drop(progress);
+    let flagged: Vec<usize> = (0..entries.len())
+        .filter(|&index| !audit_issues(&plaintexts, &entries, index, min_score).is_empty())
+        .collect();
+    for &index in &flagged {
+        println!(
+            "{}: {}",
+            entries[index].account,
+            audit_issues(&plaintexts, &entries, index, min_score).join("; ")
+        );
+    }
+    println!("{} dari {} entri bermasalah.", flagged.len(), entries.len());
+    if !fix {
+        return Ok(());
+    }
+    let policy = GeneratorPolicy::from_env()?;
+    let mut replaced = 0;
+    for index in flagged {
+        // Fixing one side of a reused pair can clear the other, so issues are re-checked here.
+        let issues = audit_issues(&plaintexts, &entries, index, min_score);
+        if issues.is_empty() || plaintexts[index].is_none() {
+            continue;
+        }
+        let prompt = format!(
+            "Buat ulang password '{}' ({})? [y/N/q] ",
+            entries[index].account,
+            issues.join("; ")
+        );
+        match confirm(&prompt)? {
+            Some(true) => {}
+            Some(false) => continue,
+            None => break,
+        }
+        let password = Zeroizing::new(generate_password(&policy));
+        let now = unix_now();
+        let previous = std::mem::replace(
+            &mut entries[index].password,
+            encrypt_password(&cipher, &password)?,
+        );
+        entries[index].push_history(previous, now);
+        entries[index].updated_at = now;
+        save_entries(data_file, &entries, &cipher)?;
+        plaintexts[index] = Some(password);
+        replaced += 1;
+        println!(
+            "Password '{}' diganti. Lihat dengan perintah `get`.",
+            entries[index].account
+        );
+    }
+    println!("{} password dibuat ulang.", replaced);
+    Ok(())
+}
+
+/// Loads `.env` without ever overriding variables that are already set in the environment,
+/// and reports where `PASSWORD_MANAGER_KEY` came from so a stray `.env` cannot go unnoticed.
+fn load_dotenv(verbose: bool) {
+    const KEY: &str = "PASSWORD_MANAGER_KEY";
+    let from_env = env::var(KEY).ok().map(Zeroizing::new);
+    // The key is cleared while `.env` loads so its own value can be compared; every other
+    // variable already in the environment is left alone, which `dotenv` never overrides.
+    env::remove_var(KEY);
+    let path = dotenv()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| ".env".to_string());
+    let from_file = env::var(KEY).ok().map(Zeroizing::new);
+    if let Some(value) = &from_env {
+        env::set_var(KEY, value.as_str());
+    }
+    match (&from_env, &from_file) {
+        (Some(env_value), Some(file_value))
+            if !bool::from(env_value.as_bytes().ct_eq(file_value.as_bytes())) =>
+        {
+            eprintln!(
+                "Peringatan: {} di environment berbeda dengan di {}; nilai dari environment yang dipakai.",
+                KEY, path
+            );
+        }
+        _ => {}
+    }
+    if verbose {
+        match (from_env, from_file) {
+            (Some(_), _) => eprintln!("{} diambil dari environment.", KEY),
+            (None, Some(_)) => eprintln!("{} diambil dari {}.", KEY, path),
+            (None, None) => eprintln!("{} tidak diset; passphrase akan diminta.", KEY),
+        }
+    }
+}
+
+/// Puts the terminal back into a usable state before the default hook prints the panic.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            DisableBracketedPaste,
+            cursor::Show
+        );
+        default_hook(info);
+    }));
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    install_panic_hook();
+    let cli = Cli::parse();
+    load_dotenv(cli.verbose);
+    let data_file = resolve_vault_path(cli.vault)?;
+    let data_file = data_file.as_str();
+    match cli.command {
+        Some(Command::Get { account }) => run_get(data_file, &account),
+        Some(Command::List) => run_list(data_file),
+        Some(Command::ExportJson { path }) => run_export_json(data_file, &path),
+        Some(Command::ImportJson { path }) => run_import_json(data_file, &path),
+        Some(Command::ImportCsv { path }) => run_import_csv(data_file, &path),
+        Some(Command::ImportToken { token }) => run_import_token(data_file, &token),
+        Some(Command::ChangeKey) => run_change_key(data_file),
+        Some(Command::Audit { min_score, fix }) => run_audit(data_file, min_score, fix),
+        Some(Command::Restore { number }) => run_restore(data_file, number),
+        None => run_tui(data_file, cli.debug),
+    }
+}
+
+fn draw_master_prompt<B: Backend>(
+    terminal: &mut Terminal<B>,
+    theme: &Theme,
+    data_file: &str,
+    input: &str,
+    hint: &str,
+    error: Option<&str>,
+) -> io::Result<()> {
+    terminal.draw(|f| {
+        let outer = theme.block(format!("Password Manager - {}", vault_name(data_file)));
+        f.render_widget(outer, f.area());
+        let area = centered_rect(60, 30, f.area());
+        f.render_widget(Clear, area);
+        let mut lines = vec![
+            Line::from("*".repeat(input.chars().count())),
+            Line::from(Span::styled(
+                hint.to_string(),
+                Style::default().fg(theme.muted),
+            )),
+            Line::from(Span::styled(
+                "Enter = buka, Esc = keluar",
+                Style::default().fg(theme.muted),
+            )),
+        ];
+        if let Some(error) = error {
+            lines.push(Line::from(Span::styled(
+                error.to_string(),
+                Style::default().fg(theme.error),
+            )));
+        }
+        let prompt = Paragraph::new(Text::from(lines))
+            .block(theme.block("Passphrase Master"))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(prompt, area);
+    })?;
+    Ok(())
+}
+
+/// Asks for the master passphrase inside the TUI when `PASSWORD_MANAGER_KEY` is not set.
+/// A vault that does not exist yet asks for the passphrase twice so a typo cannot become its
+/// key. Returns `None` when the user presses Esc.
+fn prompt_master_vault<B: Backend>(
+    terminal: &mut Terminal<B>,
+    data_file: &str,
+) -> Result<Option<Vault>, Box<dyn Error>> {
+    let (themes, _) = load_themes();
+    let theme = themes.into_iter().next().unwrap_or_default();
+    let new_vault = !Path::new(data_file).exists();
+    let mut input = Zeroizing::new(String::new());
+    let mut first: Option<Zeroizing<String>> = None;
+    let mut error: Option<String> = None;
+    let mut failed_attempts = 0;
+    let mut retry_at: Option<Instant> = None;
+    loop {
+        let hint = match (&first, new_vault) {
+            (Some(_), _) => "Ulangi passphrase untuk vault baru",
+            (None, true) => "Buat passphrase master untuk vault baru",
+            (None, false) => "Masukkan passphrase master",
+        };
+        draw_master_prompt(terminal, &theme, data_file, &input, hint, error.as_deref())?;
+        if !event::poll(Duration::from_millis(200))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Enter => {
+                if let Some(remaining) =
+                    retry_at.and_then(|at| at.checked_duration_since(Instant::now()))
+                {
+                    error = Some(App::unlock_wait_message(remaining));
+                    continue;
+                }
+                if input.trim().is_empty() {
+                    error = Some("Passphrase tidak boleh kosong.".to_string());
+                    continue;
+                }
+                let passphrase = std::mem::take(&mut input);
+                if new_vault {
+                    match first.take() {
+                        None => {
+                            first = Some(passphrase);
+                            error = None;
+                            continue;
+                        }
+                        Some(expected)
+                            if !bool::from(expected.as_bytes().ct_eq(passphrase.as_bytes())) =>
+                        {
+                            error = Some("Passphrase tidak sama, ulangi dari awal.".to_string());
+                            continue;
+                        }
+                        Some(_) => {}
+                    }
+                }
+                match load_vault(data_file, &passphrase) {
+                    Ok(vault) => return Ok(Some(vault)),
+                    Err(err) if err == WRONG_PASSPHRASE => {
+                        failed_attempts += 1;
+                        retry_at = Some(Instant::now() + unlock_backoff(failed_attempts));
+                        error = Some(err);
+                    }
+                    Err(err) => error = Some(err),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn restore_terminal<B: Backend + Write>(terminal: &mut Terminal<B>) -> Result<(), Box<dyn Error>> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+fn run_tui(data_file: &str, debug: bool) -> Result<(), Box<dyn Error>> {
+    let keymap = load_keymap().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let password_policy = PasswordPolicy::from_env().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let generator_policy = GeneratorPolicy::from_env().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let audit_log = open_audit_log().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let vault_lock = lock_vault(data_file).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let vault = match env_passphrase() {
+        Some(_) => Some(open_vault(data_file)?),
+        None => None,
+    };
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let vault = match vault {
+        Some(vault) => vault,
+        None => match prompt_master_vault(&mut terminal, data_file)? {
+            Some(vault) => vault,
+            None => return restore_terminal(&mut terminal),
+        },
+    };
+    let Vault {
+        entries,
+        cipher,
+        mutated,
+        corrupt,
+        tampered,
+    } = vault;
+    let mut app = App::new(entries, cipher, data_file);
+    app.keymap = keymap;
+    app.vault_lock = Some(vault_lock);
+    app.audit_log = audit_log;
+    app.password_policy = password_policy;
+    app.generator_policy = generator_policy;
+    app.debug = debug;
+    if let Some(warning) = load_warning(&corrupt, tampered) {
+        app.set_feedback(warning, FeedbackKind::Error);
+    }
+    if mutated {
+        if let Err(err) = app.save() {
+            app.dirty = true;
+            app.set_feedback(
+                format!("Error menyimpan ulang entri terenkripsi: {}", err),
+                FeedbackKind::Error,
+            );
+        }
+    }
+
+    loop {
+        app.poll_saves();
+        ui(&mut terminal, &mut app)?;
 
         if event::poll(Duration::from_millis(200))? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            if let Event::Resize(_, _) = event {
+                // Forces a full repaint so no cells from the old size are left behind.
+                terminal.clear()?;
+            }
+            if let Event::Mouse(mouse) = event {
+                app.touch();
+                app.handle_mouse(mouse);
+            }
+            if let Event::Paste(text) = &event {
+                app.touch();
+                app.paste_input(text);
+            }
+            if let Event::Key(key) = event {
+                app.touch();
                 match app.input_mode {
-                    InputMode::Normal => match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Down => app.next(),
-                        KeyCode::Up => app.previous(),
-                        KeyCode::Char('a') => {
+                    InputMode::Normal if !app.index_input.is_empty() => match key.code {
+                        KeyCode::Char(c) if c.is_ascii_digit() => app.push_index_digit(c),
+                        KeyCode::Backspace => {
+                            app.index_input.pop();
+                        }
+                        KeyCode::Enter => app.submit_index(false),
+                        KeyCode::Char('c') => app.submit_index(true),
+                        _ => app.index_input.clear(),
+                    },
+                    InputMode::Normal if app.jump_pending => app.finish_jump(key.code),
+                    InputMode::Normal
+                        if key.modifiers.contains(KeyModifiers::SHIFT)
+                            && matches!(key.code, KeyCode::Up | KeyCode::Down) =>
+                    {
+                        app.move_selected(key.code == KeyCode::Up)
+                    }
+                    InputMode::Normal => match app.keymap.action(&key) {
+                        Some(Action::Quit) => {
+                            app.wait_for_saves();
+                            if app.dirty {
+                                app.input_mode = InputMode::ConfirmQuit;
+                            } else {
+                                app.prepare_quit();
+                                break;
+                            }
+                        }
+                        Some(Action::Next) => app.next(),
+                        Some(Action::Previous) => app.previous(),
+                        Some(Action::Add) => {
                             app.input_mode = InputMode::EditingAccount;
                         }
-                        KeyCode::Char('v') => {
-                            if let Some(entry) = app.entries.get(app.selected) {
-                                match decrypt_password(&app.cipher, &entry.password) {
-                                    Ok(plain) => {
-                                        app.set_feedback(
-                                            format!("Password untuk {}: {}", entry.account, plain),
-                                            FeedbackKind::Info,
-                                        );
-                                    }
-                                    Err(err) => {
-                                        app.set_feedback(err, FeedbackKind::Error);
+                        Some(Action::Search) => {
+                            app.input_mode = InputMode::Searching;
+                        }
+                        Some(Action::Delete) => {
+                            if app.selected_entry().is_some() {
+                                app.input_mode = InputMode::ConfirmDelete;
+                            }
+                        }
+                        Some(Action::Reveal) => app.reveal_selected(),
+                        None => match key.code {
+                            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                match app.save() {
+                                    Ok(()) => {
+                                        app.set_feedback("Vault disimpan.", FeedbackKind::Success)
                                     }
+                                    Err(msg) => app.set_feedback(msg, FeedbackKind::Error),
+                                }
+                            }
+                            KeyCode::PageDown => app.page_down(),
+                            KeyCode::PageUp => app.page_up(),
+                            KeyCode::Home => app.first(),
+                            KeyCode::End => app.last(),
+                            KeyCode::Esc if !app.search_query.is_empty() => app.clear_filter(),
+                            KeyCode::Char('e') => {
+                                if let Err(err) = app.start_edit() {
+                                    app.set_feedback(err, FeedbackKind::Error);
                                 }
                             }
+                            KeyCode::Char('c') => app.copy_selected_password(),
+                            KeyCode::Char('C') => app.copy_selected_username(),
+                            KeyCode::Char('o') => app.open_selected_url(),
+                            KeyCode::Char('s') => app.cycle_sort(),
+                            KeyCode::Char('g') => app.cycle_tag_filter(),
+                            KeyCode::Char('t') => app.cycle_theme(),
+                            KeyCode::Char('V') => app.open_vault_switcher(),
+                            KeyCode::Char('H') => app.open_password_history(),
+                            KeyCode::Char('x') => app.open_share_token(),
+                            KeyCode::Char('N') => app.open_notes_view(),
+                            KeyCode::Char(c) if c.is_ascii_digit() => app.push_index_digit(c),
+                            KeyCode::Char('d')
+                                if app.debug
+                                    && key.modifiers.contains(KeyModifiers::CONTROL)
+                                    && app.selected_entry().is_some() =>
+                            {
+                                app.input_mode = InputMode::DebugBlob
+                            }
+                            KeyCode::Char('\'') => app.jump_pending = true,
+                            _ => {}
+                        },
+                    },
+                    InputMode::EditingAccount => match key.code {
+                        KeyCode::Esc => app.cancel_input(),
+                        KeyCode::Enter => app.advance_required(InputMode::EditingUsername),
+                        code => app.edit_input(code),
+                    },
+                    InputMode::EditingUsername => match key.code {
+                        KeyCode::Esc => app.cancel_input(),
+                        KeyCode::Enter => app.focus_field(InputMode::EditingPassword),
+                        code => app.edit_input(code),
+                    },
+                    InputMode::EditingPassword => match key.code {
+                        KeyCode::Esc => app.cancel_input(),
+                        KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.password_input.zeroize();
+                            app.password_input = generate_password(&app.generator_policy);
+                            app.input_error = None;
+                            app.cursor_pos = app.password_input.chars().count();
+                        }
+                        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.password_input.zeroize();
+                            app.password_input = generate_passphrase(&app.passphrase_policy);
+                            app.input_error = None;
+                            app.cursor_pos = app.password_input.chars().count();
+                        }
+                        KeyCode::Char('h') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.show_password = !app.show_password;
+                        }
+                        KeyCode::Enter => app.advance_required(InputMode::EditingUrl),
+                        code => app.edit_input(code),
+                    },
+                    InputMode::EditingUrl => match key.code {
+                        KeyCode::Esc => app.cancel_input(),
+                        KeyCode::Enter => app.focus_field(InputMode::EditingNotes),
+                        code => app.edit_input(code),
+                    },
+                    InputMode::EditingNotes => match key.code {
+                        KeyCode::Esc => app.cancel_input(),
+                        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.focus_field(InputMode::EditingTags)
+                        }
+                        KeyCode::Enter => app.edit_input(KeyCode::Char('\n')),
+                        KeyCode::Up => app.move_cursor_line(-1),
+                        KeyCode::Down => app.move_cursor_line(1),
+                        code => app.edit_input(code),
+                    },
+                    InputMode::EditingTags => match key.code {
+                        KeyCode::Esc => app.cancel_input(),
+                        KeyCode::Enter => app.focus_field(InputMode::EditingTotp),
+                        code => app.edit_input(code),
+                    },
+                    InputMode::EditingTotp => match key.code {
+                        KeyCode::Esc => app.cancel_input(),
+                        KeyCode::Enter => app.submit_entry(),
+                        KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.policy_override = true;
+                            app.submit_entry();
+                        }
+                        code => app.edit_input(code),
+                    },
+                    InputMode::Locked => match key.code {
+                        KeyCode::Enter => match app.unlock() {
+                            Ok(()) => app.set_feedback("Vault dibuka.", FeedbackKind::Success),
+                            Err(err) => app.set_feedback(err, FeedbackKind::Error),
+                        },
+                        KeyCode::Esc => app.passphrase_input.zeroize(),
+                        KeyCode::Char(c) => app.passphrase_input.push(c),
+                        KeyCode::Backspace => {
+                            app.passphrase_input.pop();
                         }
                         _ => {}
                     },
-                    InputMode::EditingAccount => match key.code {
-                        KeyCode::Esc => {
-                            app.input_mode = InputMode::Normal;
-                            app.account_input.clear();
+                    InputMode::VaultSwitcher => match key.code {
+                        KeyCode::Esc => app.input_mode = InputMode::Normal,
+                        KeyCode::Down => app.move_vault_selection(true),
+                        KeyCode::Up => app.move_vault_selection(false),
+                        KeyCode::Enter => app.input_mode = InputMode::VaultPassphrase,
+                        _ => {}
+                    },
+                    InputMode::DebugBlob => match key.code {
+                        KeyCode::Esc | KeyCode::Enter => app.input_mode = InputMode::Normal,
+                        _ => {}
+                    },
+                    InputMode::ShareToken if app.share_token.is_some() => match key.code {
+                        KeyCode::Esc | KeyCode::Enter => app.close_share_token(),
+                        _ => {}
+                    },
+                    InputMode::ShareToken => match key.code {
+                        KeyCode::Esc => app.close_share_token(),
+                        KeyCode::Enter => app.export_selected_token(),
+                        KeyCode::Char(c) => app.passphrase_input.push(c),
+                        KeyCode::Backspace => {
+                            app.passphrase_input.pop();
                         }
-                        KeyCode::Enter => {
-                            app.input_mode = InputMode::EditingPassword;
+                        _ => {}
+                    },
+                    InputMode::NotesView => match key.code {
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('N') => {
+                            app.input_mode = InputMode::Normal
                         }
-                        KeyCode::Char(c) => {
-                            app.account_input.push(c);
+                        KeyCode::Down => app.notes_scroll = app.notes_scroll.saturating_add(1),
+                        KeyCode::Up => app.notes_scroll = app.notes_scroll.saturating_sub(1),
+                        KeyCode::PageDown => app.notes_scroll = app.notes_scroll.saturating_add(10),
+                        KeyCode::PageUp => app.notes_scroll = app.notes_scroll.saturating_sub(10),
+                        KeyCode::Home => app.notes_scroll = 0,
+                        _ => {}
+                    },
+                    InputMode::PasswordHistory => match key.code {
+                        KeyCode::Esc => app.input_mode = InputMode::Normal,
+                        KeyCode::Down => app.move_history_selection(true),
+                        KeyCode::Up => app.move_history_selection(false),
+                        KeyCode::Char('c') => app.copy_history_password(),
+                        KeyCode::Char('r') => {
+                            match app.restore_history_password() {
+                                Ok(account) => app.set_feedback(
+                                    format!("Password lama '{}' dipulihkan.", account),
+                                    FeedbackKind::Success,
+                                ),
+                                Err(err) => app.set_feedback(err, FeedbackKind::Error),
+                            }
+                            app.input_mode = InputMode::Normal;
                         }
+                        _ => {}
+                    },
+                    InputMode::VaultPassphrase => match key.code {
+                        KeyCode::Enter => match app.switch_vault() {
+                            Ok((name, None)) => app.set_feedback(
+                                format!("Vault '{}' dibuka.", name),
+                                FeedbackKind::Success,
+                            ),
+                            Ok((name, Some(warning))) => app.set_feedback(
+                                format!("Vault '{}' dibuka. {}", name, warning),
+                                FeedbackKind::Error,
+                            ),
+                            Err(err) => app.set_feedback(err, FeedbackKind::Error),
+                        },
+                        KeyCode::Esc => {
+                            app.passphrase_input.zeroize();
+                            app.input_mode = InputMode::VaultSwitcher;
+                        }
+                        KeyCode::Char(c) => app.passphrase_input.push(c),
                         KeyCode::Backspace => {
-                            app.account_input.pop();
+                            app.passphrase_input.pop();
                         }
                         _ => {}
                     },
-                    InputMode::EditingPassword => match key.code {
+                    InputMode::Searching => match key.code {
                         KeyCode::Esc => {
+                            app.clear_filter();
                             app.input_mode = InputMode::Normal;
-                            app.password_input.clear();
                         }
-                        KeyCode::Enter => match app.add_entry() {
-                            Ok(_) => {
-                                if let Err(e) = save_entries(data_file, &app.entries) {
-                                    app.set_feedback(
-                                        format!("Error menyimpan entri: {}", e),
-                                        FeedbackKind::Error,
-                                    );
+                        KeyCode::Enter => app.commit_search(),
+                        KeyCode::Down => app.next(),
+                        KeyCode::Up => app.previous(),
+                        KeyCode::Char(c) => {
+                            app.search_query.push(c);
+                            app.update_search();
+                        }
+                        KeyCode::Backspace => {
+                            app.search_query.pop();
+                            app.update_search();
+                        }
+                        _ => {}
+                    },
+                    InputMode::ConfirmQuit => match key.code {
+                        KeyCode::Char('s') => match app.save() {
+                            Ok(()) => {
+                                app.wait_for_saves();
+                                if app.dirty {
+                                    app.input_mode = InputMode::Normal;
                                 } else {
-                                    app.set_feedback(
-                                        "Entri berhasil ditambahkan dan password terenkripsi.",
-                                        FeedbackKind::Success,
-                                    );
+                                    app.prepare_quit();
+                                    break;
                                 }
-                                app.input_mode = InputMode::Normal;
                             }
                             Err(msg) => {
                                 app.set_feedback(msg, FeedbackKind::Error);
+                                app.input_mode = InputMode::Normal;
                             }
                         },
-                        KeyCode::Char(c) => {
-                            app.password_input.push(c);
+                        KeyCode::Char('b') => {
+                            app.prepare_quit();
+                            break;
                         }
-                        KeyCode::Backspace => {
-                            app.password_input.pop();
+                        KeyCode::Char('n') | KeyCode::Esc => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        _ => {}
+                    },
+                    InputMode::ConfirmDuplicate => match key.code {
+                        KeyCode::Char('y') => app.update_duplicate(),
+                        KeyCode::Char('n') | KeyCode::Esc => app.rename_duplicate(),
+                        _ => {}
+                    },
+                    InputMode::ConfirmDelete => match key.code {
+                        KeyCode::Char('y') => {
+                            match app.delete_entry() {
+                                Ok(removed) if app.entries.is_empty() => {
+                                    let text = format!(
+                                        "Entri '{}' dihapus. Vault sekarang kosong, tekan {} untuk menambah entri.",
+                                        removed.account,
+                                        app.keymap.label(Action::Add)
+                                    );
+                                    app.set_feedback(text, FeedbackKind::Info);
+                                    app.audit("delete", &removed.account);
+                                }
+                                Ok(removed) => {
+                                    app.set_feedback(
+                                        format!("Entri '{}' berhasil dihapus.", removed.account),
+                                        FeedbackKind::Success,
+                                    );
+                                    app.audit("delete", &removed.account);
+                                }
+                                Err(msg) => {
+                                    app.set_feedback(msg, FeedbackKind::Error);
+                                }
+                            }
+                            app.input_mode = InputMode::Normal;
+                        }
+                        KeyCode::Char('n') | KeyCode::Esc => {
+                            app.input_mode = InputMode::Normal;
                         }
                         _ => {}
                     },
                 }
             }
         }
+        app.tick(Instant::now());
     }
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    restore_terminal(&mut terminal)?;
+    if let Some(lock) = app.vault_lock.take() {
+        let _ = lock.unlock();
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kept out of the working directory in case a test ends up saving.
+    fn test_vault_path() -> String {
+        env::temp_dir()
+            .join(format!("pmv-test-{}-app.txt", std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Fixed settings and no clipboard, so no test reads the host's environment, config
+    /// directory or system clipboard.
+    fn test_settings() -> Settings {
+        Settings {
+            themes: vec![Theme::dark()],
+            theme_error: None,
+            clipboard_clear_after: Duration::from_secs(15),
+            reveal_after: None,
+            page_size: None,
+            duplicate_policy: DuplicatePolicy::Prompt,
+            auto_lock_after: None,
+            password_max_age_days: None,
+            hibp_dir: None,
+        }
+    }
+
+    fn app_with_entries(entries: Vec<Entry>, cipher: VaultCipher) -> App {
+        App::with_settings(entries, cipher, &test_vault_path(), test_settings(), None)
+    }
+
+    fn test_app() -> App {
+        app_with_entries(Vec::new(), VaultCipher::new([7; 32]))
+    }
+
+    fn feedback_text(app: &App) -> Option<&str> {
+        app.feedback.as_ref().map(|feedback| feedback.text.as_str())
+    }
+
+    /// The whole allocation of `text`, including capacity left behind by a clear.
+    fn allocation(text: &String) -> Vec<u8> {
+        unsafe { std::slice::from_raw_parts(text.as_ptr(), text.capacity()) }.to_vec()
+    }
+
+    #[test]
+    fn cancel_input_zeroizes_buffers() {
+        let mut app = test_app();
+        app.account_input = "bank".to_string();
+        app.password_input = "hunter2".to_string();
+        app.input_mode = InputMode::EditingPassword;
+
+        app.cancel_input();
+
+        for buffer in [&app.account_input, &app.password_input] {
+            assert!(buffer.is_empty() && buffer.capacity() > 0);
+            assert!(allocation(buffer).iter().all(|&byte| byte == 0));
+        }
+    }
+
+    #[test]
+    fn revealed_password_never_reaches_feedback() {
+        let cipher = VaultCipher::new([7; 32]);
+        let entry = Entry {
+            account: "bank".to_string(),
+            password: encrypt_password(&cipher, "hunter2").unwrap(),
+            ..Entry::default()
+        };
+        let mut app = app_with_entries(vec![entry], cipher);
+        let holds_plaintext = |app: &App| feedback_text(app).is_some_and(|t| t.contains("hunter2"));
+
+        app.reveal_selected();
+        assert!(app.revealed);
+        assert!(!holds_plaintext(&app));
+
+        app.copy_selected_password();
+        assert!(app.clipboard_digest.is_none());
+        assert!(app.timers.clipboard_clear.is_none());
+        assert_eq!(
+            feedback_text(&app),
+            Some("Clipboard tidak tersedia, password bank ditampilkan di panel detail.")
+        );
+
+        app.reveal_selected();
+        app.lock();
+        assert!(!app.revealed);
+        assert!(app.feedback.is_none());
+    }
+
+    fn app_with_accounts(accounts: &[&str]) -> App {
+        let cipher = VaultCipher::new([7; 32]);
+        let entries = accounts
+            .iter()
+            .map(|account| Entry {
+                account: account.to_string(),
+                password: encrypt_password(&cipher, "hunter2").unwrap(),
+                ..Entry::default()
+            })
+            .collect();
+        app_with_entries(entries, cipher)
+    }
+
+    #[test]
+    fn entry_exists_ignores_case_and_whitespace() {
+        let mut app = app_with_accounts(&["GitHub", "bank"]);
+
+        assert!(app.entry_exists("GitHub"));
+        assert!(app.entry_exists("github"));
+        assert!(app.entry_exists("BANK"));
+        assert!(app.entry_exists("  github \t"));
+        assert!(!app.entry_exists("gitlab"));
+
+        app.editing_index = Some(0);
+        assert!(!app.entry_exists("github"));
+        assert!(app.entry_exists("Bank"));
+    }
+
+    #[test]
+    fn add_entry_rejects_existing_account() {
+        let mut app = app_with_accounts(&["GitHub"]);
+        app.account_input = " github ".to_string();
+        app.password_input = "S3cure-Passw0rd!".to_string();
+        app.policy_override = true;
+
+        assert!(app.add_entry().is_err());
+        assert_eq!(app.entries.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_account_prompts_or_rejects_by_policy() {
+        let mut app = app_with_accounts(&["GitHub"]);
+        app.account_input = "github".to_string();
+        app.password_input = "S3cure-Passw0rd!".to_string();
+
+        app.duplicate_policy = DuplicatePolicy::Prompt;
+        app.submit_entry();
+        assert!(matches!(app.input_mode, InputMode::ConfirmDuplicate));
+        assert_eq!(app.duplicate_of, Some(0));
+        assert_eq!(app.entries.len(), 1);
+
+        app.duplicate_of = None;
+        app.duplicate_policy = DuplicatePolicy::Reject;
+        app.submit_entry();
+        assert!(matches!(app.input_mode, InputMode::EditingAccount));
+        assert!(app.duplicate_of.is_none());
+        assert!(app
+            .feedback
+            .as_ref()
+            .is_some_and(|f| matches!(f.kind, FeedbackKind::Error)));
+        assert_eq!(app.entries.len(), 1);
+    }
+
+    #[test]
+    fn account_sort_ignores_case_across_scripts() {
+        let mut accounts = vec!["github", "Bank", "ÉCOLE", "apple", "Zoom", "école", "Ωmega"];
+        accounts.sort_by_cached_key(|account| account_sort_key(account));
+        assert_eq!(
+            accounts,
+            vec!["apple", "Bank", "github", "Zoom", "ÉCOLE", "école", "Ωmega"]
+        );
+
+        assert_eq!(account_sort_key("ÄRZTE"), account_sort_key("ärzte"));
+        assert_eq!(account_sort_key("ΣΟΦΙΑ"), account_sort_key("σοφια"));
+    }
+
+    #[test]
+    fn tag_filter_cycles_through_matching_subsets() {
+        let mut app = app_with_accounts(&["gmail", "bank", "github", "notes"]);
+        app.entries[0].tags = vec!["personal".to_string()];
+        app.entries[1].tags = vec!["banking".to_string(), "personal".to_string()];
+        app.entries[2].tags = vec!["work".to_string()];
+        app.refresh_filter();
+        let visible = |app: &App| -> Vec<String> {
+            app.filtered_indices
+                .iter()
+                .map(|&index| app.entries[index].account.clone())
+                .collect()
+        };
+
+        app.cycle_tag_filter();
+        assert_eq!(app.tag_filter, Some(TagFilter::Tag("banking".to_string())));
+        assert_eq!(visible(&app), vec!["bank"]);
+
+        app.cycle_tag_filter();
+        assert_eq!(visible(&app), vec!["gmail", "bank"]);
+
+        app.cycle_tag_filter();
+        assert_eq!(visible(&app), vec!["github"]);
+
+        app.cycle_tag_filter();
+        assert_eq!(app.tag_filter, Some(TagFilter::Untagged));
+        assert_eq!(visible(&app), vec!["notes"]);
+
+        app.cycle_tag_filter();
+        assert_eq!(app.tag_filter, None);
+        assert_eq!(visible(&app).len(), 4);
+    }
+
+    #[test]
+    fn reuse_check_and_note_search_use_cached_plaintext() {
+        let mut app = app_with_accounts(&["bank", "mail"]);
+        assert_eq!(app.reused_by("hunter2", Some(0)).as_deref(), Some("mail"));
+        assert_eq!(app.reused_by("hunter3", None), None);
+        app.entries[1].notes = encrypt_notes(&app.cipher, "kode cadangan").unwrap();
+        app.search_query = "cadangan".to_string();
+        app.apply_filter();
+        assert_eq!(app.filtered_indices, vec![1]);
+        // Both lookups are now served without the key.
+        app.cipher = VaultCipher::new([8; 32]);
+        app.apply_filter();
+        assert_eq!(app.filtered_indices, vec![1]);
+        let password = app.entries[1].password.clone();
+        assert!(app.password_digests.digest(&app.cipher, &password).is_ok());
+    }
+
+    #[test]
+    fn revealed_password_rows_split_by_display_width() {
+        assert_eq!(split_by_width("ab界c", 3), vec!["ab", "界c"]);
+        assert_eq!(split_by_width("界界", 3), vec!["界", "界"]);
+        assert_eq!(split_by_width("a b  c", 3), vec!["a b", "  c"]);
+        assert_eq!(split_by_width("", 3), vec![""]);
+    }
+
+    #[test]
+    fn jump_prefix_reaches_letters_bound_to_actions() {
+        let mut app = app_with_accounts(&["bank", "apple", "dash"]);
+        for (letter, account) in [('a', "apple"), ('D', "dash"), ('b', "bank")] {
+            app.jump_pending = true;
+            app.finish_jump(KeyCode::Char(letter));
+            assert!(!app.jump_pending);
+            assert_eq!(
+                app.selected_entry().map(|e| e.account.as_str()),
+                Some(account)
+            );
+        }
+        app.jump_pending = true;
+        app.finish_jump(KeyCode::Esc);
+        assert!(!app.jump_pending);
+        assert_eq!(
+            app.selected_entry().map(|e| e.account.as_str()),
+            Some("bank")
+        );
+
+        let mut keymap = Keymap::default();
+        keymap.bind(Action::Add, vec![KeyCode::Char('\'')]);
+        assert!(keymap.validate().is_err());
+    }
+
+    #[test]
+    fn rejected_duplicate_update_returns_to_input() {
+        let mut app = app_with_accounts(&["GitHub"]);
+        app.account_input = "github".to_string();
+        app.password_input = "S3cure-Passw0rd!".to_string();
+        app.totp_input = "not base32!".to_string();
+        app.submit_entry();
+        assert!(matches!(app.input_mode, InputMode::ConfirmDuplicate));
+
+        app.update_duplicate();
+        assert!(matches!(app.input_mode, InputMode::EditingTotp));
+        assert!(app
+            .feedback
+            .as_ref()
+            .is_some_and(|f| matches!(f.kind, FeedbackKind::Error)));
+
+        // The update stays confirmed: fixing the field updates the entry without a new prompt.
+        app.totp_input.clear();
+        assert_eq!(app.editing_index, Some(0));
+        assert!(!app.check_duplicate());
+        assert!(app.add_entry().is_ok());
+        assert_eq!(app.entries.len(), 1);
+        assert_eq!(app.entries[0].account, "github");
+    }
+
+    #[test]
+    fn tick_clears_clipboard_at_deadline() {
+        let mut app = test_app();
+        let start = Instant::now();
+        app.clipboard_digest = Some([1; 32]);
+        app.timers.clipboard_clear = Some(start + Duration::from_secs(15));
+
+        app.tick(start + Duration::from_secs(14));
+        assert!(app.timers.clipboard_clear.is_some());
+        assert!(app.clipboard_digest.is_some());
+
+        app.tick(start + Duration::from_secs(15));
+        assert!(app.timers.clipboard_clear.is_none());
+        assert!(app.clipboard_digest.is_none());
+        assert_eq!(feedback_text(&app), Some("Clipboard dibersihkan."));
+    }
+
+    #[test]
+    fn tick_hides_revealed_password_at_deadline() {
+        let mut app = test_app();
+        let start = Instant::now();
+        app.revealed = true;
+        app.timers.reveal_hide = Some(start + Duration::from_secs(10));
+
+        app.tick(start + Duration::from_secs(9));
+        assert!(app.revealed);
+
+        app.tick(start + Duration::from_secs(10));
+        assert!(!app.revealed);
+        assert!(app.timers.reveal_hide.is_none());
+        assert_eq!(feedback_text(&app), Some("Password disembunyikan kembali."));
+    }
+
+    #[test]
+    fn tick_locks_after_inactivity() {
+        let mut app = test_app();
+        let start = Instant::now();
+        app.revealed = true;
+        app.passphrase_input = "draft".to_string();
+        app.timers.auto_lock = Some(start + Duration::from_secs(300));
+
+        app.tick(start + Duration::from_secs(299));
+        assert!(matches!(app.input_mode, InputMode::Normal));
+
+        app.tick(start + Duration::from_secs(300));
+        assert!(matches!(app.input_mode, InputMode::Locked));
+        assert!(!app.revealed);
+        assert!(app.passphrase_input.is_empty());
+        assert!(app.timers.auto_lock.is_none());
+    }
+}
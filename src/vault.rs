@@ -0,0 +1,1981 @@
+//! Vault storage without any UI: the entry model, encryption and the on-disk format.
+//!
+//! The TUI binary is built on top of this module; other tools can use it to read and
+//! write the same vault files.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use aes_gcm::{aead::Aead, aead::KeyInit, Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::ChaCha20Poly1305;
+use fs2::FileExt;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::{Zeroize, Zeroizing};
+
+/// One vault record; `password` and `notes` hold encrypted values, never the plaintext.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Entry {
+    pub account: String,
+    pub username: String,
+    pub password: String,
+    pub url: String,
+    /// Encrypted like `password`; empty when the entry has no notes.
+    pub notes: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub totp_secret: Option<String>,
+    pub tags: Vec<String>,
+    /// Previous encrypted passwords, newest first.
+    pub history: Vec<PasswordHistory>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PasswordHistory {
+    pub password: String,
+    pub replaced_at: u64,
+}
+
+impl Entry {
+    pub fn push_history(&mut self, password: String, replaced_at: u64) {
+        self.history.insert(
+            0,
+            PasswordHistory {
+                password,
+                replaced_at,
+            },
+        );
+        self.history.truncate(PASSWORD_HISTORY_LEN);
+    }
+
+    pub fn matches_tag(&self, filter: &TagFilter) -> bool {
+        match filter {
+            TagFilter::Tag(tag) => self.tags.contains(tag),
+            TagFilter::Untagged => self.tags.is_empty(),
+        }
+    }
+
+    pub fn age_days(&self, now: u64) -> Option<u64> {
+        (self.updated_at > 0).then(|| now.saturating_sub(self.updated_at) / SECS_PER_DAY)
+    }
+
+    pub fn is_stale(&self, now: u64, max_age_days: Option<u64>) -> bool {
+        matches!((self.age_days(now), max_age_days), (Some(age), Some(max)) if age >= max)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagFilter {
+    Tag(String),
+    Untagged,
+}
+
+impl TagFilter {
+    pub fn label(&self) -> &str {
+        match self {
+            TagFilter::Tag(tag) => tag,
+            TagFilter::Untagged => "(untagged)",
+        }
+    }
+}
+
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+    {
+        if !tags.iter().any(|existing| existing == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Account names pasted from web pages may carry zero-width characters or a different Unicode
+/// normalization, so they are stored as NFC without those. Passwords are kept byte-exact.
+pub fn normalize_account(input: &str) -> String {
+    let normalized: String = input
+        .nfc()
+        .filter(|c| !c.is_control() && !is_zero_width(*c))
+        .collect();
+    normalized.trim().to_string()
+}
+
+fn is_zero_width(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}' | '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}'
+    )
+}
+
+pub const SECS_PER_DAY: u64 = 24 * 60 * 60;
+pub const PASSWORD_HISTORY_LEN: usize = 3;
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+pub const SALT_LEN: usize = 16;
+pub const ENTRY_SALT_LEN: usize = 16;
+/// Plain format: the body is a JSON [`VaultFile`] whose entries keep every secret encrypted.
+pub const FORMAT_VERSION: u32 = 5;
+/// The previous plain format, one CSV record per entry. Upgraded on the next save.
+const CSV_FORMAT_VERSION: u32 = 4;
+/// The CSV format before notes were encrypted. Upgraded on the next save.
+const PLAIN_NOTES_VERSION: u32 = 2;
+/// Format where the whole JSON body, account names included, is one encrypted blob.
+pub const SEALED_FORMAT_VERSION: u32 = 6;
+/// The previous sealed format, whose blob holds CSV records with plaintext notes.
+const SEALED_CSV_VERSION: u32 = 3;
+pub const FORMAT_HEADER_PREFIX: &str = "# pmv:";
+pub const VERIFY_HEADER_PREFIX: &str = "# verify:";
+pub const VERIFY_SENTINEL: &str = "VERIFY";
+/// Last line of the file: an HMAC-SHA256 over every byte before it.
+pub const MAC_TRAILER_PREFIX: &str = "# mac:";
+pub const TAMPERED_WARNING: &str =
+    "Checksum vault tidak cocok: file rusak atau diubah di luar aplikasi.";
+pub const WRONG_PASSPHRASE: &str = "Passphrase salah.";
+/// The AEAD a vault's entries are encrypted with, recorded after the version in its header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CipherAlgorithm {
+    #[default]
+    #[serde(rename = "aes256gcm")]
+    Aes256Gcm,
+    #[serde(rename = "chacha20poly1305")]
+    ChaCha20Poly1305,
+}
+
+impl CipherAlgorithm {
+    /// The header marker; AES-256-GCM has none so older vaults keep their header unchanged.
+    fn marker(self) -> Option<&'static str> {
+        match self {
+            CipherAlgorithm::Aes256Gcm => None,
+            CipherAlgorithm::ChaCha20Poly1305 => Some("chacha20poly1305"),
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<CipherAlgorithm> {
+        match value {
+            "aes256gcm" => Some(CipherAlgorithm::Aes256Gcm),
+            "chacha20poly1305" => Some(CipherAlgorithm::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CipherAlgorithm::Aes256Gcm => "AES-256-GCM",
+            CipherAlgorithm::ChaCha20Poly1305 => "ChaCha20-Poly1305",
+        }
+    }
+}
+
+enum EntryCipher {
+    Aes(Box<Aes256Gcm>),
+    ChaCha(ChaCha20Poly1305),
+}
+
+impl EntryCipher {
+    fn encrypt(&self, nonce: [u8; 12], plaintext: &[u8]) -> aes_gcm::aead::Result<Vec<u8>> {
+        let nonce = Nonce::from(nonce);
+        match self {
+            EntryCipher::Aes(cipher) => cipher.encrypt(&nonce, plaintext),
+            EntryCipher::ChaCha(cipher) => cipher.encrypt(&nonce, plaintext),
+        }
+    }
+
+    fn decrypt(&self, nonce: [u8; 12], ciphertext: &[u8]) -> aes_gcm::aead::Result<Vec<u8>> {
+        let nonce = Nonce::from(nonce);
+        match self {
+            EntryCipher::Aes(cipher) => cipher.decrypt(&nonce, ciphertext),
+            EntryCipher::ChaCha(cipher) => cipher.decrypt(&nonce, ciphertext),
+        }
+    }
+}
+
+/// The master key of an unlocked vault; each entry is encrypted with a key derived from it.
+#[derive(Clone)]
+pub struct VaultCipher {
+    key: [u8; 32],
+    master: Aes256Gcm,
+    algorithm: CipherAlgorithm,
+}
+
+impl VaultCipher {
+    pub fn new(key: [u8; 32]) -> VaultCipher {
+        VaultCipher::with_algorithm(key, CipherAlgorithm::Aes256Gcm)
+    }
+
+    pub fn with_algorithm(key: [u8; 32], algorithm: CipherAlgorithm) -> VaultCipher {
+        let master = Aes256Gcm::new(&key.into());
+        VaultCipher {
+            key,
+            master,
+            algorithm,
+        }
+    }
+
+    pub fn algorithm(&self) -> CipherAlgorithm {
+        self.algorithm
+    }
+
+    fn entry_cipher(&self, salt: &[u8]) -> Result<EntryCipher, String> {
+        let hkdf = Hkdf::<Sha256>::new(Some(salt), &self.key);
+        let mut entry_key = Zeroizing::new([0u8; 32]);
+        hkdf.expand(b"pmv2-entry", entry_key.as_mut())
+            .map_err(|_| "Gagal menurunkan kunci entri.".to_string())?;
+        Ok(match self.algorithm {
+            CipherAlgorithm::Aes256Gcm => {
+                EntryCipher::Aes(Box::new(Aes256Gcm::new(entry_key.as_ref().into())))
+            }
+            CipherAlgorithm::ChaCha20Poly1305 => {
+                EntryCipher::ChaCha(ChaCha20Poly1305::new(entry_key.as_ref().into()))
+            }
+        })
+    }
+
+    fn file_mac(&self, contents: &[u8]) -> Result<Hmac<Sha256>, String> {
+        let hkdf = Hkdf::<Sha256>::new(None, &self.key);
+        let mut mac_key = Zeroizing::new([0u8; 32]);
+        hkdf.expand(b"pmv-file-mac", mac_key.as_mut())
+            .map_err(|_| "Gagal menurunkan kunci checksum vault.".to_string())?;
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(mac_key.as_ref())
+            .map_err(|_| "Gagal menurunkan kunci checksum vault.".to_string())?;
+        mac.update(contents);
+        Ok(mac)
+    }
+
+    /// A keyed digest of `plaintext`, so secrets can be compared without keeping them
+    /// decrypted; without the vault key the digest says nothing about the plaintext.
+    pub fn secret_digest(&self, plaintext: &str) -> Result<[u8; 32], String> {
+        let hkdf = Hkdf::<Sha256>::new(None, &self.key);
+        let mut digest_key = Zeroizing::new([0u8; 32]);
+        hkdf.expand(b"pmv-secret-digest", digest_key.as_mut())
+            .map_err(|_| "Gagal menurunkan kunci digest.".to_string())?;
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(digest_key.as_ref())
+            .map_err(|_| "Gagal menurunkan kunci digest.".to_string())?;
+        mac.update(plaintext.as_bytes());
+        Ok(mac.finalize().into_bytes().into())
+    }
+
+    /// Compares `key` with this cipher's key in constant time.
+    pub fn key_matches(&self, key: &[u8]) -> bool {
+        bool::from(key.ct_eq(&self.key))
+    }
+}
+
+impl Drop for VaultCipher {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+pub struct KeyMigration {
+    legacy_cipher: VaultCipher,
+    salt: [u8; SALT_LEN],
+}
+
+pub fn salt_path(data_file: &str) -> String {
+    format!("{}.salt", data_file)
+}
+
+fn lock_path(data_file: &str) -> String {
+    format!("{}.lock", data_file)
+}
+
+/// Takes an exclusive lock on the vault's `.lock` sidecar for as long as the file is held.
+/// The lock cannot sit on the vault itself because every save replaces the vault inode.
+pub fn lock_vault(data_file: &str) -> Result<File, String> {
+    let path = lock_path(data_file);
+    ensure_parent_dir(&path).map_err(|e| format!("Gagal membuat direktori vault: {}", e))?;
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .map_err(|e| format!("Gagal membuka file lock {}: {}", path, e))?;
+    file.try_lock_exclusive().map_err(|_| {
+        format!(
+            "Vault {} sedang dibuka oleh instance lain. Tutup instance tersebut terlebih dahulu.",
+            data_file
+        )
+    })?;
+    Ok(file)
+}
+
+pub fn file_mtime(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+pub fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let params = Params::new(19 * 1024, 2, 1, Some(32))
+        .map_err(|e| format!("Parameter Argon2 tidak valid: {}", e))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    let mut key = [0u8; 32];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Gagal menurunkan kunci: {}", e))?;
+    Ok(key)
+}
+
+fn legacy_cipher(passphrase: &str) -> VaultCipher {
+    VaultCipher::new(Sha256::digest(passphrase.as_bytes()).into())
+}
+
+pub fn read_salt(path: &str) -> Result<[u8; SALT_LEN], String> {
+    let encoded =
+        fs::read_to_string(path).map_err(|e| format!("Gagal membaca file salt: {}", e))?;
+    let bytes = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| "File salt tidak valid.".to_string())?;
+    bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| "Panjang salt tidak valid.".to_string())
+}
+
+fn ensure_parent_dir(path: &str) -> io::Result<()> {
+    match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+fn write_salt(path: &str, salt: &[u8]) -> io::Result<()> {
+    ensure_parent_dir(path)?;
+    fs::write(path, general_purpose::STANDARD.encode(salt))
+}
+
+/// Like [`initialize_cipher_with`], using the algorithm recorded in the vault header. A vault
+/// that does not exist yet uses `VAULT_CIPHER`, falling back to AES-256-GCM.
+pub fn initialize_cipher(
+    data_file: &str,
+    passphrase: &str,
+) -> Result<(VaultCipher, Option<KeyMigration>), String> {
+    let algorithm = vault_algorithm(data_file)?
+        .or_else(requested_algorithm)
+        .unwrap_or_default();
+    initialize_cipher_with(data_file, passphrase, algorithm)
+}
+
+/// Derives the vault cipher with Argon2id using the salt stored next to the vault.
+///
+/// Vaults created before the salt file existed were keyed with a plain SHA-256 digest;
+/// for those a [`KeyMigration`] is returned so the caller can re-encrypt the entries and
+/// persist the new salt once loading succeeds.
+pub fn initialize_cipher_with(
+    data_file: &str,
+    passphrase: &str,
+    algorithm: CipherAlgorithm,
+) -> Result<(VaultCipher, Option<KeyMigration>), String> {
+    let salt_file = salt_path(data_file);
+    let (salt, migration) = if Path::new(&salt_file).exists() {
+        (read_salt(&salt_file)?, None)
+    } else {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        if Path::new(data_file).exists() {
+            let migration = KeyMigration {
+                legacy_cipher: legacy_cipher(passphrase),
+                salt,
+            };
+            (salt, Some(migration))
+        } else {
+            write_salt(&salt_file, &salt)
+                .map_err(|e| format!("Gagal menyimpan file salt: {}", e))?;
+            (salt, None)
+        }
+    };
+    let key = derive_key(passphrase, &salt)?;
+    Ok((VaultCipher::with_algorithm(key, algorithm), migration))
+}
+
+pub fn reencrypt_entries(
+    entries: &mut [Entry],
+    old_cipher: &VaultCipher,
+    new_cipher: &VaultCipher,
+) -> Result<(), String> {
+    reencrypt_entries_with(entries, old_cipher, new_cipher, || {})
+}
+
+/// Like [`reencrypt_entries`], calling `on_entry` before each entry so callers can report
+/// progress on large vaults.
+pub fn reencrypt_entries_with(
+    entries: &mut [Entry],
+    old_cipher: &VaultCipher,
+    new_cipher: &VaultCipher,
+    mut on_entry: impl FnMut(),
+) -> Result<(), String> {
+    for entry in entries.iter_mut() {
+        on_entry();
+        let plain = decrypt_password(old_cipher, &entry.password)
+            .map_err(|e| format!("Enkripsi ulang '{}' gagal: {}", entry.account, e))?;
+        entry.password = encrypt_password(new_cipher, &plain)?;
+        if let Some(secret) = &entry.totp_secret {
+            let plain = decrypt_password(old_cipher, secret)
+                .map_err(|e| format!("Enkripsi ulang '{}' gagal: {}", entry.account, e))?;
+            entry.totp_secret = Some(encrypt_password(new_cipher, &plain)?);
+        }
+        let notes = decrypt_notes(old_cipher, &entry.notes)
+            .map_err(|e| format!("Enkripsi ulang '{}' gagal: {}", entry.account, e))?;
+        entry.notes = encrypt_notes(new_cipher, &notes)?;
+        for old in entry.history.iter_mut() {
+            let plain = decrypt_password(old_cipher, &old.password)
+                .map_err(|e| format!("Enkripsi ulang '{}' gagal: {}", entry.account, e))?;
+            old.password = encrypt_password(new_cipher, &plain)?;
+        }
+    }
+    Ok(())
+}
+
+/// Encrypts `plaintext` under a fresh entry salt and nonce as `salt:nonce:ciphertext`.
+pub fn encrypt_password(cipher: &VaultCipher, plaintext: &str) -> Result<String, String> {
+    let mut salt = [0u8; ENTRY_SALT_LEN];
+    let mut nonce_bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let ciphertext = cipher
+        .entry_cipher(&salt)?
+        .encrypt(nonce_bytes, plaintext.as_bytes())
+        .map_err(|e| format!("Gagal mengenkripsi password: {}", e))?;
+    let encoded_salt = general_purpose::STANDARD.encode(salt);
+    let encoded_nonce = general_purpose::STANDARD.encode(nonce_bytes);
+    let encoded_cipher = general_purpose::STANDARD.encode(ciphertext);
+    Ok(format!(
+        "{}:{}:{}",
+        encoded_salt, encoded_nonce, encoded_cipher
+    ))
+}
+
+/// Encrypts an entry's notes; empty notes stay empty so entries without notes cost nothing.
+pub fn encrypt_notes(cipher: &VaultCipher, notes: &str) -> Result<String, String> {
+    if notes.is_empty() {
+        return Ok(String::new());
+    }
+    encrypt_password(cipher, notes)
+}
+
+/// The counterpart of [`encrypt_notes`].
+pub fn decrypt_notes(cipher: &VaultCipher, notes: &str) -> Result<Zeroizing<String>, String> {
+    if notes.is_empty() {
+        return Ok(Zeroizing::new(String::new()));
+    }
+    decrypt_password(cipher, notes)
+}
+
+pub struct EncryptedComponents {
+    /// Per-entry salt; `None` for values written before format v2 (`nonce:cipher`).
+    pub salt: Option<Vec<u8>>,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Splits and base64-decodes an encrypted value without decrypting it.
+pub fn decode_encrypted_components(value: &str) -> Result<EncryptedComponents, String> {
+    let parts: Vec<&str> = value.split(':').collect();
+    let (salt_b64, nonce_b64, cipher_b64) = match parts.as_slice() {
+        [nonce, cipher] => (None, *nonce, *cipher),
+        [salt, nonce, cipher] => (Some(*salt), *nonce, *cipher),
+        _ => return Err("Format data enkripsi tidak valid.".to_string()),
+    };
+    let salt_bytes = match salt_b64 {
+        Some(salt_b64) => {
+            let salt = general_purpose::STANDARD
+                .decode(salt_b64)
+                .map_err(|_| "Salt entri tidak valid.".to_string())?;
+            if salt.len() != ENTRY_SALT_LEN {
+                return Err("Panjang salt entri tidak valid.".to_string());
+            }
+            Some(salt)
+        }
+        None => None,
+    };
+    let nonce_bytes = general_purpose::STANDARD
+        .decode(nonce_b64)
+        .map_err(|_| "Nonce terenkripsi tidak valid.".to_string())?;
+    if nonce_bytes.len() != 12 {
+        return Err("Panjang nonce tidak valid.".to_string());
+    }
+    let cipher_bytes = general_purpose::STANDARD
+        .decode(cipher_b64)
+        .map_err(|_| "Ciphertext terenkripsi tidak valid.".to_string())?;
+    Ok(EncryptedComponents {
+        salt: salt_bytes,
+        nonce: nonce_bytes,
+        ciphertext: cipher_bytes,
+    })
+}
+
+/// Decrypts a value produced by [`encrypt_password`], including pre-v2 `nonce:ciphertext`.
+pub fn decrypt_password(cipher: &VaultCipher, value: &str) -> Result<Zeroizing<String>, String> {
+    let components = decode_encrypted_components(value)?;
+    let nonce_array: [u8; 12] = components
+        .nonce
+        .as_slice()
+        .try_into()
+        .map_err(|_| "Nonce terenkripsi tidak valid.".to_string())?;
+    let ciphertext = components.ciphertext.as_slice();
+    let plaintext = match components.salt {
+        Some(salt) => cipher.entry_cipher(&salt)?.decrypt(nonce_array, ciphertext),
+        None => cipher.master.decrypt(&Nonce::from(nonce_array), ciphertext),
+    }
+    .map(Zeroizing::new)
+    .map_err(|_| "Gagal mendekripsi password.".to_string())?;
+    std::str::from_utf8(&plaintext)
+        .map(|plain| Zeroizing::new(plain.to_string()))
+        .map_err(|_| "Password terdekripsi bukan UTF-8 valid.".to_string())
+}
+
+const PLAINTEXT_CACHE_SIZE: usize = 16;
+
+/// Recently decrypted secrets keyed by their ciphertext. Saves write existing ciphertexts back
+/// unchanged, so cached values stay valid across saves, while a changed secret is encrypted
+/// with a fresh nonce and therefore gets a new key; a stale plaintext can never be returned.
+/// Evicted values are zeroized on drop.
+#[derive(Default)]
+pub struct PlaintextCache {
+    items: VecDeque<(String, Zeroizing<String>)>,
+}
+
+impl PlaintextCache {
+    pub fn decrypt(
+        &mut self,
+        cipher: &VaultCipher,
+        value: &str,
+    ) -> Result<Zeroizing<String>, String> {
+        if let Some(position) = self.items.iter().position(|(key, _)| key == value) {
+            let item = self.items.remove(position).expect("position is in bounds");
+            let plain = item.1.clone();
+            self.items.push_front(item);
+            return Ok(plain);
+        }
+        let plain = decrypt_password(cipher, value)?;
+        self.items.push_front((value.to_string(), plain.clone()));
+        self.items.truncate(PLAINTEXT_CACHE_SIZE);
+        Ok(plain)
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+/// Keyed digests of stored passwords, keyed by ciphertext like [`PlaintextCache`], so the
+/// password reuse check decrypts each stored password at most once instead of on every add.
+#[derive(Default)]
+pub struct PasswordDigests {
+    items: HashMap<String, [u8; 32]>,
+}
+
+impl PasswordDigests {
+    pub fn digest(&mut self, cipher: &VaultCipher, value: &str) -> Result<[u8; 32], String> {
+        if let Some(digest) = self.items.get(value) {
+            return Ok(*digest);
+        }
+        let digest = cipher.secret_digest(&decrypt_password(cipher, value)?)?;
+        self.items.insert(value.to_string(), digest);
+        Ok(digest)
+    }
+
+    /// Drops the digests of ciphertexts that are no longer stored.
+    pub fn retain(&mut self, entries: &[Entry]) {
+        let in_use: std::collections::HashSet<&str> = entries
+            .iter()
+            .map(|entry| entry.password.as_str())
+            .collect();
+        self.items
+            .retain(|value, _| in_use.contains(value.as_str()));
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+/// Whether `value` looks like an encrypted password rather than legacy plaintext.
+pub fn is_encrypted_format(value: &str) -> bool {
+    let parts: Vec<&str> = value.split(':').collect();
+    (parts.len() == 2 || parts.len() == 3) && parts.iter().all(|part| !part.is_empty())
+}
+
+fn parse_format_header(line: &str) -> Option<io::Result<(u32, CipherAlgorithm)>> {
+    let mut fields = line.strip_prefix(FORMAT_HEADER_PREFIX)?.split_whitespace();
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Header format vault tidak valid: {}", line),
+        )
+    };
+    let version = fields.next().and_then(|v| v.parse::<u32>().ok());
+    let algorithm = match fields.next() {
+        Some(marker) => CipherAlgorithm::parse(marker),
+        None => Some(CipherAlgorithm::Aes256Gcm),
+    };
+    Some(match (version, algorithm, fields.next()) {
+        (Some(version), Some(algorithm), None) => Ok((version, algorithm)),
+        _ => Err(invalid()),
+    })
+}
+
+fn format_header(version: u32, algorithm: CipherAlgorithm) -> String {
+    match algorithm.marker() {
+        Some(marker) => format!("{}{} {}", FORMAT_HEADER_PREFIX, version, marker),
+        None => format!("{}{}", FORMAT_HEADER_PREFIX, version),
+    }
+}
+
+fn read_first_line(path: &str) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut first_line = String::new();
+    io::BufRead::read_line(&mut io::BufReader::new(file), &mut first_line).ok()?;
+    Some(first_line.trim_end().to_string())
+}
+
+/// The algorithm recorded in the header of the vault at `path`, or `None` if it does not
+/// exist yet. Vaults without a header predate the choice and use AES-256-GCM.
+pub fn vault_algorithm(path: &str) -> Result<Option<CipherAlgorithm>, String> {
+    let Some(first_line) = read_first_line(path) else {
+        return Ok(None);
+    };
+    match parse_format_header(&first_line) {
+        Some(parsed) => parsed
+            .map(|(_, algorithm)| Some(algorithm))
+            .map_err(|e| e.to_string()),
+        None => Ok(Some(CipherAlgorithm::Aes256Gcm)),
+    }
+}
+
+/// The algorithm requested through `VAULT_CIPHER` for new vaults and `change-key`.
+pub fn requested_algorithm() -> Option<CipherAlgorithm> {
+    CipherAlgorithm::parse(env::var("VAULT_CIPHER").ok()?.trim())
+}
+
+/// The format requested through `VAULT_FORMAT`, or `None` to keep the file's current one.
+fn requested_sealed_format() -> Option<bool> {
+    match env::var("VAULT_FORMAT").ok()?.trim() {
+        "sealed" => Some(true),
+        "json" | "csv" => Some(false),
+        _ => None,
+    }
+}
+
+/// How vault files are read and written, normally taken from `VAULT_FORMAT`,
+/// `VAULT_INTEGRITY` and `BACKUP_KEEP`.
+#[derive(Debug, Clone, Copy)]
+pub struct StorageOptions {
+    /// The format to convert to on the next save, or `None` to keep the file's current one.
+    pub sealed: Option<bool>,
+    pub strict_integrity: bool,
+    pub backup_keep: usize,
+}
+
+impl StorageOptions {
+    pub fn from_env() -> StorageOptions {
+        StorageOptions {
+            sealed: requested_sealed_format(),
+            strict_integrity: strict_integrity(),
+            backup_keep: backup_keep(),
+        }
+    }
+}
+
+fn is_sealed_version(version: u32) -> bool {
+    matches!(version, SEALED_FORMAT_VERSION | SEALED_CSV_VERSION)
+}
+
+fn is_sealed_vault(path: &str) -> bool {
+    let Some(first_line) = read_first_line(path) else {
+        return false;
+    };
+    matches!(
+        parse_format_header(&first_line),
+        Some(Ok((version, _))) if is_sealed_version(version)
+    )
+}
+
+/// The JSON document behind the vault header, as written by [`save_entries`].
+#[derive(Serialize)]
+pub struct VaultFile<'a> {
+    /// Always the version in the file's header, so a decrypted sealed body describes itself.
+    pub version: u32,
+    pub entries: &'a [Entry],
+}
+
+/// A [`VaultFile`] as read back: entries stay raw so a bad one is skipped, not the vault.
+#[derive(Deserialize)]
+struct RawVaultFile<'a> {
+    version: u32,
+    #[serde(borrow)]
+    entries: Vec<&'a serde_json::value::RawValue>,
+}
+
+pub fn serialize_vault_file(version: u32, entries: &[Entry]) -> Result<Zeroizing<String>, String> {
+    serde_json::to_string_pretty(&VaultFile { version, entries })
+        .map(Zeroizing::new)
+        .map_err(|e| format!("Gagal menyusun isi vault: {}", e))
+}
+
+/// An entry read back from a vault body with the 1-based line it starts on, or why it is bad.
+pub type NumberedEntry = (usize, Result<Entry, String>);
+
+/// Parses a [`VaultFile`] body; the document must be valid, single entries may not be.
+pub fn parse_vault_file(body: &str, version: u32) -> Result<Vec<NumberedEntry>, String> {
+    let file: RawVaultFile = serde_json::from_str(body)
+        .map_err(|e| format!("Isi vault bukan JSON yang valid: {}", e))?;
+    if file.version != version {
+        return Err(format!(
+            "Versi isi vault {} tidak cocok dengan header ({}).",
+            file.version, version
+        ));
+    }
+    Ok(file
+        .entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, raw)| {
+            // `raw` borrows from `body`, so its offset gives the line the entry starts on.
+            let offset = raw.get().as_ptr() as usize - body.as_ptr() as usize;
+            let line = body[..offset].matches('\n').count() + 1;
+            let entry = serde_json::from_str::<Entry>(raw.get())
+                .map_err(|_| format!("entri ke-{} tidak valid", index + 1));
+            (line, entry)
+        })
+        .collect())
+}
+
+/// Rejects entries whose ciphertext cannot even be decoded; only the encoding is checked so
+/// loading never decrypts, and bad ciphertext surfaces on use.
+fn check_entry(mut entry: Entry) -> Result<Entry, String> {
+    let malformed = |value: &str| decode_encrypted_components(value).is_err();
+    if malformed(&entry.password) {
+        return Err(format!("password '{}' tidak valid", entry.account));
+    }
+    if entry.totp_secret.as_deref().is_some_and(malformed) {
+        return Err(format!("secret TOTP '{}' tidak valid", entry.account));
+    }
+    if !entry.notes.is_empty() && malformed(&entry.notes) {
+        return Err(format!("catatan '{}' tidak valid", entry.account));
+    }
+    entry.history.retain(|old| !malformed(&old.password));
+    entry.history.truncate(PASSWORD_HISTORY_LEN);
+    Ok(entry)
+}
+
+/// Parses CSV records, pairing each with the 1-based line it starts on.
+pub fn parse_csv_records(input: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut field_started = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => {
+                in_quotes = true;
+                field_started = true;
+            }
+            ',' => {
+                record.push(std::mem::take(&mut field));
+                field_started = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if field_started || !field.is_empty() {
+                    record.push(std::mem::take(&mut field));
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                field_started = false;
+                line += 1;
+                record_line = line;
+            }
+            _ => {
+                field.push(c);
+                field_started = true;
+            }
+        }
+    }
+    if in_quotes {
+        return Err(format!(
+            "Tanda kutip pada baris {} tidak ditutup.",
+            record_line
+        ));
+    }
+    if field_started || !field.is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+    Ok(records)
+}
+
+fn split_first_line(text: &str) -> Option<(&str, &str)> {
+    if text.is_empty() {
+        return None;
+    }
+    let (line, rest) = text.split_once('\n').unwrap_or((text, ""));
+    Some((line.trim_end_matches('\r'), rest))
+}
+
+/// Splits off the `# mac:` trailer, returning the bytes it covers and its encoded value.
+fn split_mac_trailer(content: &str) -> (&str, Option<&str>) {
+    let trimmed = content.trim_end_matches(['\n', '\r']);
+    let start = trimmed.rfind('\n').map_or(0, |index| index + 1);
+    match trimmed[start..].strip_prefix(MAC_TRAILER_PREFIX) {
+        Some(mac) => (&content[..start], Some(mac.trim())),
+        None => (content, None),
+    }
+}
+
+/// `VAULT_INTEGRITY=strict` refuses to open a vault whose checksum does not match.
+fn strict_integrity() -> bool {
+    env::var("VAULT_INTEGRITY").is_ok_and(|value| value.trim() == "strict")
+}
+
+fn wrong_passphrase_error() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, WRONG_PASSPHRASE)
+}
+
+/// A wrong passphrase is rejected by AES-GCM authentication, which fails the same way for any
+/// bad key; the plaintext check after it is constant-time so it leaks nothing either.
+pub fn verify_sentinel(cipher: &VaultCipher, token: &str) -> io::Result<()> {
+    match decrypt_password(cipher, token) {
+        Ok(plain) if bool::from(plain.as_bytes().ct_eq(VERIFY_SENTINEL.as_bytes())) => Ok(()),
+        _ => Err(wrong_passphrase_error()),
+    }
+}
+
+pub struct CorruptLine {
+    pub line: usize,
+    pub reason: String,
+}
+
+pub fn corrupt_summary(corrupt: &[CorruptLine]) -> String {
+    let lines: Vec<String> = corrupt.iter().map(|c| c.line.to_string()).collect();
+    format!(
+        "{} entri rusak dilewati (baris {}).",
+        corrupt.len(),
+        lines.join(", ")
+    )
+}
+
+/// What went wrong while loading, if anything: a checksum mismatch and skipped lines.
+pub fn load_warning(corrupt: &[CorruptLine], tampered: bool) -> Option<String> {
+    let mut parts = Vec::new();
+    if tampered {
+        parts.push(TAMPERED_WARNING.to_string());
+    }
+    if !corrupt.is_empty() {
+        parts.push(corrupt_summary(corrupt));
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+pub struct LoadedEntries {
+    pub entries: Vec<Entry>,
+    pub updated: bool,
+    pub corrupt: Vec<CorruptLine>,
+    /// The `# mac:` trailer did not match the file contents.
+    pub tampered: bool,
+}
+
+/// Reads the vault at `path`; unreadable lines are skipped and reported in `corrupt`.
+pub fn load_entries(path: &str, cipher: &VaultCipher) -> io::Result<LoadedEntries> {
+    load_entries_with(path, cipher, &StorageOptions::from_env())
+}
+
+fn load_entries_with(
+    path: &str,
+    cipher: &VaultCipher,
+    options: &StorageOptions,
+) -> io::Result<LoadedEntries> {
+    let mut entries = Vec::new();
+    let mut updated = false;
+    let mut corrupt = Vec::new();
+    if !Path::new(path).exists() {
+        return Ok(LoadedEntries {
+            entries,
+            updated,
+            corrupt,
+            tampered: false,
+        });
+    }
+    let content = fs::read_to_string(path)?;
+    let (content, mac) = split_mac_trailer(&content);
+    let sealed_body: Zeroizing<String>;
+    let mut body = content;
+    let mut version = 1;
+    let mut sentinel = None;
+    let mut header_lines = 0;
+    if let Some((first_line, rest)) = split_first_line(body) {
+        if let Some(parsed) = parse_format_header(first_line) {
+            let (parsed_version, algorithm) = parsed?;
+            if algorithm != cipher.algorithm() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Vault dienkripsi dengan {}, bukan {}.",
+                        algorithm.label(),
+                        cipher.algorithm().label()
+                    ),
+                ));
+            }
+            version = parsed_version;
+            body = rest;
+            header_lines += 1;
+            if let Some((line, rest)) = split_first_line(body) {
+                if let Some(token) = line.strip_prefix(VERIFY_HEADER_PREFIX) {
+                    sentinel = Some(token.to_string());
+                    body = rest;
+                    header_lines += 1;
+                }
+            }
+        }
+    }
+    let sealed = is_sealed_version(version);
+    if sealed {
+        let blob = body.trim();
+        decode_encrypted_components(blob).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Isi vault terenkripsi tidak valid.",
+            )
+        })?;
+        sealed_body = decrypt_password(cipher, blob).map_err(|_| wrong_passphrase_error())?;
+        body = sealed_body.as_str();
+        header_lines = 0;
+    }
+    if options.sealed.is_some_and(|wanted| wanted != sealed) {
+        updated = true;
+    }
+    let json = matches!(version, FORMAT_VERSION | SEALED_FORMAT_VERSION);
+    let json_entries = if json {
+        parse_vault_file(body, version)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+    } else {
+        // Every line-based format is rewritten as JSON on the next save.
+        updated = true;
+        Vec::new()
+    };
+    let records: Vec<(usize, Vec<String>)> = match version {
+        _ if json => Vec::new(),
+        1 => body
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| match line.split_once(',') {
+                Some((account, password)) => {
+                    (index + 1, vec![account.to_string(), password.to_string()])
+                }
+                None => (index + 1, vec![line.to_string()]),
+            })
+            .collect(),
+        PLAIN_NOTES_VERSION | CSV_FORMAT_VERSION | SEALED_CSV_VERSION => {
+            parse_csv_records(body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        }
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Versi format vault {} tidak didukung.", other),
+            ))
+        }
+    };
+    match &sentinel {
+        Some(token) => verify_sentinel(cipher, token)?,
+        None if sealed => {}
+        None => {
+            // Vaults written before the sentinel existed are checked against their first entry
+            // so a wrong passphrase never gets baked into a freshly written sentinel.
+            let first_encrypted = records
+                .iter()
+                .find_map(|(_, record)| match record.as_slice() {
+                    [_, password, ..] if is_encrypted_format(password) => Some(password),
+                    _ => None,
+                })
+                .or_else(|| {
+                    json_entries
+                        .iter()
+                        .find_map(|(_, entry)| entry.as_ref().ok().map(|entry| &entry.password))
+                });
+            if let Some(password) = first_encrypted {
+                decrypt_password(cipher, password).map_err(|_| wrong_passphrase_error())?;
+            }
+            updated = true;
+        }
+    }
+    // Checked only after the passphrase is known to be right, so a wrong key is never
+    // reported as tampering.
+    let tampered = match mac {
+        Some(mac) => {
+            let expected = general_purpose::STANDARD.decode(mac).unwrap_or_default();
+            cipher
+                .file_mac(content.as_bytes())
+                .map_err(io::Error::other)?
+                .verify_slice(&expected)
+                .is_err()
+        }
+        None => {
+            updated = true;
+            false
+        }
+    };
+    if tampered && options.strict_integrity {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, TAMPERED_WARNING));
+    }
+    for (line, entry) in json_entries {
+        match entry.and_then(check_entry) {
+            Ok(entry) => entries.push(entry),
+            Err(reason) => corrupt.push(CorruptLine {
+                line: line + header_lines,
+                reason,
+            }),
+        }
+    }
+    for (line, record) in records {
+        let line = line + header_lines;
+        let [account, raw_password, extra @ ..] = record.as_slice() else {
+            corrupt.push(CorruptLine {
+                line,
+                reason: "kolom password tidak ada".to_string(),
+            });
+            continue;
+        };
+        let totp_secret = extra.get(5).filter(|v| !v.is_empty()).cloned();
+        // Only the encoding is checked so loading never decrypts; bad ciphertext surfaces on use.
+        let malformed = |value: &String| decode_encrypted_components(value).is_err();
+        if is_encrypted_format(raw_password) && malformed(raw_password) {
+            corrupt.push(CorruptLine {
+                line,
+                reason: format!("password '{}' tidak valid", account),
+            });
+            continue;
+        }
+        if totp_secret.as_ref().is_some_and(malformed) {
+            corrupt.push(CorruptLine {
+                line,
+                reason: format!("secret TOTP '{}' tidak valid", account),
+            });
+            continue;
+        }
+        let raw_notes = extra.get(2).cloned().unwrap_or_default();
+        let notes = if raw_notes.is_empty() {
+            raw_notes
+        } else if version == CSV_FORMAT_VERSION {
+            if malformed(&raw_notes) {
+                corrupt.push(CorruptLine {
+                    line,
+                    reason: format!("catatan '{}' tidak valid", account),
+                });
+                continue;
+            }
+            raw_notes
+        } else {
+            // Older plain vaults and the sealed CSV body keep notes as plaintext.
+            encrypt_notes(cipher, &raw_notes).map_err(io::Error::other)?
+        };
+        let password = if is_encrypted_format(raw_password) {
+            raw_password.clone()
+        } else {
+            encrypt_password(cipher, raw_password).map_err(io::Error::other)?
+        };
+        entries.push(Entry {
+            account: account.clone(),
+            username: extra.first().cloned().unwrap_or_default(),
+            password,
+            url: extra.get(1).cloned().unwrap_or_default(),
+            notes,
+            created_at: extra.get(3).and_then(|v| v.parse().ok()).unwrap_or(0),
+            updated_at: extra.get(4).and_then(|v| v.parse().ok()).unwrap_or(0),
+            totp_secret,
+            tags: extra.get(6).map(|v| parse_tags(v)).unwrap_or_default(),
+            history: extra.get(7).map(|v| parse_history(v)).unwrap_or_default(),
+        });
+    }
+    Ok(LoadedEntries {
+        entries,
+        updated,
+        corrupt,
+        tampered,
+    })
+}
+
+/// Parses `replaced_at@salt:nonce:ciphertext` items separated by `;`, dropping malformed ones
+/// so a damaged history never costs the entry itself.
+fn parse_history(value: &str) -> Vec<PasswordHistory> {
+    value
+        .split(';')
+        .filter_map(|item| {
+            let (replaced_at, password) = item.split_once('@')?;
+            decode_encrypted_components(password).ok()?;
+            Some(PasswordHistory {
+                password: password.to_string(),
+                replaced_at: replaced_at.parse().ok()?,
+            })
+        })
+        .take(PASSWORD_HISTORY_LEN)
+        .collect()
+}
+
+fn write_entries(
+    path: &str,
+    entries: &[Entry],
+    cipher: &VaultCipher,
+    sealed: bool,
+) -> io::Result<()> {
+    let version = if sealed {
+        SEALED_FORMAT_VERSION
+    } else {
+        FORMAT_VERSION
+    };
+    let body = serialize_vault_file(version, entries).map_err(io::Error::other)?;
+    let mut content = Zeroizing::new(format_header(version, cipher.algorithm()));
+    if sealed {
+        let blob = encrypt_password(cipher, &body).map_err(io::Error::other)?;
+        content.push_str(&format!("\n{}\n", blob));
+    } else {
+        let sentinel = encrypt_password(cipher, VERIFY_SENTINEL).map_err(io::Error::other)?;
+        content.push_str(&format!("\n{}{}\n", VERIFY_HEADER_PREFIX, sentinel));
+        content.push_str(&body);
+        content.push('\n');
+    }
+    let mac = cipher
+        .file_mac(content.as_bytes())
+        .map_err(io::Error::other)?
+        .finalize()
+        .into_bytes();
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // Owner-only from the moment it exists; `keep_permissions` restores the vault's own mode.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(content.as_bytes())?;
+    writeln!(
+        file,
+        "{}{}",
+        MAC_TRAILER_PREFIX,
+        general_purpose::STANDARD.encode(mac)
+    )?;
+    file.flush()?;
+    file.sync_all()
+}
+
+fn backup_keep() -> usize {
+    env::var("BACKUP_KEEP")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(5)
+}
+
+fn backup_dir(data_file: &str) -> PathBuf {
+    Path::new(data_file)
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join("backups")
+}
+
+fn backup_prefix(data_file: &str) -> String {
+    let name = Path::new(data_file)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| data_file.to_string());
+    format!("{}.bak.", name)
+}
+
+/// Backups of `data_file`, newest first.
+pub fn list_backups(data_file: &str) -> Vec<(u64, PathBuf)> {
+    let prefix = backup_prefix(data_file);
+    let mut backups: Vec<((u64, u32), PathBuf)> = fs::read_dir(backup_dir(data_file))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let order = parse_backup_suffix(name.strip_prefix(&prefix)?)?;
+            Some((order, entry.path()))
+        })
+        .collect();
+    backups.sort_by_key(|(order, _)| std::cmp::Reverse(*order));
+    backups
+        .into_iter()
+        .map(|((timestamp, _), path)| (timestamp, path))
+        .collect()
+}
+
+/// Parses `<unix>` or `<unix>-<n>`, the name given to later backups within the same second.
+fn parse_backup_suffix(suffix: &str) -> Option<(u64, u32)> {
+    let (timestamp, sequence) = suffix.split_once('-').unwrap_or((suffix, "0"));
+    Some((timestamp.parse().ok()?, sequence.parse().ok()?))
+}
+
+const MAX_BACKUPS_PER_SECOND: u32 = 100;
+
+fn backup_vault(data_file: &str) -> io::Result<()> {
+    let dir = backup_dir(data_file);
+    fs::create_dir_all(&dir)?;
+    let stem = format!("{}{}", backup_prefix(data_file), unix_now());
+    for sequence in 0..MAX_BACKUPS_PER_SECOND {
+        let name = match sequence {
+            0 => stem.clone(),
+            n => format!("{}-{}", stem, n),
+        };
+        // `create_new` so two saves within one second never overwrite each other's backup.
+        let mut target = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dir.join(name))
+        {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        };
+        target.set_permissions(fs::metadata(data_file)?.permissions())?;
+        io::copy(&mut File::open(data_file)?, &mut target)?;
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "Terlalu banyak backup vault dalam satu detik.",
+    ))
+}
+
+fn prune_backups(data_file: &str, keep: usize) {
+    for (_, path) in list_backups(data_file).into_iter().skip(keep) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Gives the freshly written `tmp_path` the permissions of the file it is about to replace,
+/// so a vault restricted with `chmod 600` is not widened to the umask default by a save.
+fn keep_permissions(path: &str, tmp_path: &str) -> io::Result<()> {
+    match fs::metadata(path) {
+        Ok(metadata) => fs::set_permissions(tmp_path, metadata.permissions()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn replace_file(path: &str, write: impl FnOnce(&str) -> io::Result<()>) -> io::Result<()> {
+    replace_file_with(path, &StorageOptions::from_env(), write)
+}
+
+fn replace_file_with(
+    path: &str,
+    options: &StorageOptions,
+    write: impl FnOnce(&str) -> io::Result<()>,
+) -> io::Result<()> {
+    ensure_parent_dir(path)?;
+    let keep = options.backup_keep;
+    if keep > 0 && Path::new(path).exists() {
+        backup_vault(path)?;
+    }
+    let tmp_path = format!("{}.tmp", path);
+    let result = write(&tmp_path)
+        .and_then(|_| keep_permissions(path, &tmp_path))
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    } else if keep > 0 {
+        prune_backups(path, keep);
+    }
+    result
+}
+
+/// Backs up the current file, then atomically replaces it with `entries`. The file stays in
+/// its current format unless `VAULT_FORMAT` asks for the other one.
+pub fn save_entries(path: &str, entries: &[Entry], cipher: &VaultCipher) -> io::Result<()> {
+    save_entries_with(path, entries, cipher, &StorageOptions::from_env())
+}
+
+fn save_entries_with(
+    path: &str,
+    entries: &[Entry],
+    cipher: &VaultCipher,
+    options: &StorageOptions,
+) -> io::Result<()> {
+    let sealed = options.sealed.unwrap_or_else(|| is_sealed_vault(path));
+    replace_file_with(path, options, |tmp_path| {
+        write_entries(tmp_path, entries, cipher, sealed)
+    })
+}
+
+pub struct Vault {
+    pub entries: Vec<Entry>,
+    pub cipher: VaultCipher,
+    pub mutated: bool,
+    pub corrupt: Vec<CorruptLine>,
+    pub tampered: bool,
+}
+
+pub fn corrupt_backup_path(data_file: &str) -> String {
+    format!("{}.corrupt", data_file)
+}
+
+/// Derives the key for `data_file` and loads it, migrating legacy vaults if needed.
+pub fn load_vault(data_file: &str, passphrase: &str) -> Result<Vault, String> {
+    let load_error = |err: io::Error| match err.kind() {
+        io::ErrorKind::PermissionDenied => err.to_string(),
+        _ => format!("Error memuat entri: {}", err),
+    };
+    let (cipher, migration) = initialize_cipher(data_file, passphrase)?;
+    let legacy_cipher = migration.as_ref().map(|m| &m.legacy_cipher);
+    let loaded = load_entries(data_file, legacy_cipher.unwrap_or(&cipher)).map_err(load_error)?;
+    // Skipped lines or a damaged file would vanish on the next save, so the original is kept
+    // aside first.
+    if !loaded.corrupt.is_empty() || loaded.tampered {
+        fs::copy(data_file, corrupt_backup_path(data_file))
+            .map_err(|e| format!("Gagal menyalin vault yang rusak: {}", e))?;
+    }
+    let LoadedEntries {
+        mut entries,
+        updated,
+        corrupt,
+        tampered,
+    } = loaded;
+    match migration {
+        Some(migration) => {
+            reencrypt_entries(&mut entries, &migration.legacy_cipher, &cipher)?;
+            save_entries(data_file, &entries, &cipher)
+                .map_err(|e| format!("Error menyimpan entri hasil migrasi: {}", e))?;
+            write_salt(&salt_path(data_file), &migration.salt)
+                .map_err(|e| format!("Gagal menyimpan file salt: {}", e))?;
+            Ok(Vault {
+                entries,
+                cipher,
+                mutated: false,
+                corrupt,
+                tampered,
+            })
+        }
+        None => Ok(Vault {
+            entries,
+            cipher,
+            mutated: updated,
+            corrupt,
+            tampered,
+        }),
+    }
+}
+
+/// First byte of every share token; bumped whenever the layout below changes.
+pub const TOKEN_VERSION: u8 = 1;
+const TOKEN_NONCE_LEN: usize = 12;
+
+/// The plaintext sealed inside a share token. Password history is left out on purpose.
+#[derive(Serialize, Deserialize)]
+struct SharedEntry {
+    account: String,
+    username: String,
+    password: String,
+    url: String,
+    notes: String,
+    totp_secret: Option<String>,
+    tags: Vec<String>,
+}
+
+impl Drop for SharedEntry {
+    fn drop(&mut self) {
+        self.password.zeroize();
+        self.notes.zeroize();
+        if let Some(secret) = self.totp_secret.as_mut() {
+            secret.zeroize();
+        }
+    }
+}
+
+fn invalid_token() -> String {
+    "Token tidak valid.".to_string()
+}
+
+/// Encrypts one entry as `base64(version || salt || nonce || ciphertext)` for handing to
+/// someone else. The AES-256-GCM key is derived from `passphrase` with Argon2id and a fresh
+/// salt, so the token is independent of the vault key.
+pub fn export_token(
+    entry: &Entry,
+    cipher: &VaultCipher,
+    passphrase: &str,
+) -> Result<String, String> {
+    let totp_secret = match &entry.totp_secret {
+        Some(secret) => Some(decrypt_password(cipher, secret)?.to_string()),
+        None => None,
+    };
+    let shared = SharedEntry {
+        account: entry.account.clone(),
+        username: entry.username.clone(),
+        password: decrypt_password(cipher, &entry.password)?.to_string(),
+        url: entry.url.clone(),
+        notes: decrypt_notes(cipher, &entry.notes)?.to_string(),
+        totp_secret,
+        tags: entry.tags.clone(),
+    };
+    let plaintext = Zeroizing::new(
+        serde_json::to_vec(&shared).map_err(|e| format!("Gagal membuat token: {}", e))?,
+    );
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; TOKEN_NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let key = Zeroizing::new(derive_key(passphrase, &salt)?);
+    let ciphertext = Aes256Gcm::new(key.as_ref().into())
+        .encrypt(&Nonce::from(nonce), plaintext.as_slice())
+        .map_err(|e| format!("Gagal mengenkripsi token: {}", e))?;
+    let mut token = vec![TOKEN_VERSION];
+    token.extend_from_slice(&salt);
+    token.extend_from_slice(&nonce);
+    token.extend_from_slice(&ciphertext);
+    Ok(general_purpose::STANDARD.encode(token))
+}
+
+/// Opens a token made by [`export_token`] and re-encrypts its secrets under `cipher`.
+pub fn import_token(token: &str, passphrase: &str, cipher: &VaultCipher) -> Result<Entry, String> {
+    let bytes = general_purpose::STANDARD
+        .decode(token.trim())
+        .map_err(|_| invalid_token())?;
+    let (&version, rest) = bytes.split_first().ok_or_else(invalid_token)?;
+    if version != TOKEN_VERSION {
+        return Err(format!("Versi token {} tidak didukung.", version));
+    }
+    if rest.len() < SALT_LEN + TOKEN_NONCE_LEN {
+        return Err(invalid_token());
+    }
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(TOKEN_NONCE_LEN);
+    let nonce: [u8; TOKEN_NONCE_LEN] = nonce.try_into().map_err(|_| invalid_token())?;
+    let key = Zeroizing::new(derive_key(passphrase, salt)?);
+    let plaintext = Aes256Gcm::new(key.as_ref().into())
+        .decrypt(&Nonce::from(nonce), ciphertext)
+        .map(Zeroizing::new)
+        .map_err(|_| "Passphrase token salah.".to_string())?;
+    let shared: SharedEntry =
+        serde_json::from_slice(&plaintext).map_err(|_| "Isi token tidak valid.".to_string())?;
+    let totp_secret = match &shared.totp_secret {
+        Some(secret) => Some(encrypt_password(cipher, secret)?),
+        None => None,
+    };
+    let now = unix_now();
+    Ok(Entry {
+        account: normalize_account(&shared.account),
+        username: shared.username.clone(),
+        password: encrypt_password(cipher, &shared.password)?,
+        url: shared.url.clone(),
+        notes: encrypt_notes(cipher, &shared.notes)?,
+        created_at: now,
+        updated_at: now,
+        totp_secret,
+        tags: shared.tags.clone(),
+        history: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fixed storage options, so no test depends on the host's `VAULT_*` or `BACKUP_KEEP`.
+    const OPTIONS: StorageOptions = StorageOptions {
+        sealed: None,
+        strict_integrity: false,
+        backup_keep: 5,
+    };
+
+    /// A fresh directory under the system temp dir, unique to this process and test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("pmv-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn failed_write_leaves_original_file_untouched() {
+        let dir = test_dir("replace-file");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        fs::write(path, "# pmv:4\noriginal contents\n").unwrap();
+        let before = fs::read(path).unwrap();
+
+        let result = replace_file_with(path, &OPTIONS, |tmp_path| {
+            fs::write(tmp_path, "# pmv:4\nhalf writ")?;
+            Err(io::Error::other("disk full"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(path).unwrap(), before);
+        assert!(!Path::new(&format!("{}.tmp", path)).exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn csv_fields_round_trip() {
+        let rows = vec![
+            vec!["My, Bank".to_string(), "plain".to_string()],
+            vec!["say \"hi\"".to_string(), "\"".to_string()],
+            vec!["line\nbreak".to_string(), String::new()],
+        ];
+        let input = "\"My, Bank\",plain\n\"say \"\"hi\"\"\",\"\"\"\"\n\"line\nbreak\",\n";
+
+        let records = parse_csv_records(input).unwrap();
+
+        let lines: Vec<usize> = records.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![1, 2, 3]);
+        let parsed: Vec<Vec<String>> = records.into_iter().map(|(_, fields)| fields).collect();
+        assert_eq!(parsed, rows);
+    }
+
+    #[test]
+    fn corrupt_lines_are_reported_and_valid_entries_load() {
+        let dir = test_dir("load-corrupt");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        let cipher = VaultCipher::new([7; 32]);
+        let password = encrypt_password(&cipher, "hunter2").unwrap();
+        let content = format!(
+            "{}\n{}{}\ngmail,{},me,,,0,0,,,\nbrokenline\nbank,abc:def:!!!,,,,0,0,,,\ngithub,{}\n",
+            format_header(CSV_FORMAT_VERSION, cipher.algorithm()),
+            VERIFY_HEADER_PREFIX,
+            encrypt_password(&cipher, VERIFY_SENTINEL).unwrap(),
+            password,
+            password,
+        );
+        fs::write(path, content).unwrap();
+
+        let loaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+
+        let accounts: Vec<&str> = loaded.entries.iter().map(|e| e.account.as_str()).collect();
+        assert_eq!(accounts, vec!["gmail", "github"]);
+        assert_eq!(loaded.entries[0].username, "me");
+        let corrupt: Vec<(usize, &str)> = loaded
+            .corrupt
+            .iter()
+            .map(|c| (c.line, c.reason.as_str()))
+            .collect();
+        assert_eq!(
+            corrupt,
+            vec![
+                (4, "kolom password tidak ada"),
+                (5, "password 'bank' tidak valid"),
+            ]
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn cipher_key_is_zeroized_on_drop() {
+        let mut slot = std::mem::MaybeUninit::new(VaultCipher::new([7; 32]));
+        // The slot outlives the drop, so the key bytes it leaves behind can still be read.
+        let key = unsafe {
+            slot.as_mut_ptr().drop_in_place();
+            slot.as_ptr()
+                .cast::<u8>()
+                .add(std::mem::offset_of!(VaultCipher, key))
+                .cast::<[u8; 32]>()
+                .read()
+        };
+        assert_eq!(key, [0; 32]);
+    }
+
+    #[test]
+    fn sentinel_and_key_checks_reject_other_keys() {
+        let cipher = VaultCipher::new([7; 32]);
+        let other = VaultCipher::new([8; 32]);
+        let token = encrypt_password(&cipher, VERIFY_SENTINEL).unwrap();
+
+        assert!(verify_sentinel(&cipher, &token).is_ok());
+        let err = verify_sentinel(&other, &token).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        let wrong_text = encrypt_password(&cipher, "NOT-VERIFY").unwrap();
+        assert!(verify_sentinel(&cipher, &wrong_text).is_err());
+
+        assert!(cipher.key_matches(&[7; 32]));
+        assert!(!cipher.key_matches(&[8; 32]));
+        assert!(!cipher.key_matches(&[7; 16]));
+    }
+
+    #[test]
+    fn normalize_account_unifies_lookalike_names() {
+        let composed = "Caf\u{e9}";
+        let decomposed = "Cafe\u{301}";
+        assert_ne!(composed, decomposed);
+        assert_eq!(normalize_account(decomposed), normalize_account(composed));
+        assert_eq!(normalize_account(decomposed), composed);
+
+        assert_eq!(normalize_account("git\u{200B}hub"), "github");
+        assert_eq!(normalize_account("\u{FEFF}github\u{2060}"), "github");
+        assert_eq!(normalize_account("  my\u{AD} bank\t\n"), "my bank");
+    }
+
+    fn sample_entry(cipher: &VaultCipher, account: &str) -> Entry {
+        Entry {
+            account: account.to_string(),
+            username: "me@example.com".to_string(),
+            password: encrypt_password(cipher, "hunter2").unwrap(),
+            url: "https://example.com/login?a=1,b=2".to_string(),
+            notes: encrypt_notes(cipher, "first line\nsecond: line").unwrap(),
+            created_at: 1_700_000_000,
+            updated_at: 1_700_000_100,
+            totp_secret: Some(encrypt_password(cipher, "JBSWY3DPEHPK3PXP").unwrap()),
+            tags: vec!["kerja".to_string(), "bank".to_string()],
+            history: Vec::new(),
+        }
+    }
+
+    fn assert_same_entries(loaded: &[Entry], saved: &[Entry], cipher: &VaultCipher) {
+        assert_eq!(loaded.len(), saved.len());
+        for (loaded, saved) in loaded.iter().zip(saved) {
+            assert_eq!(loaded.account, saved.account);
+            assert_eq!(loaded.username, saved.username);
+            assert_eq!(loaded.url, saved.url);
+            assert_eq!(loaded.tags, saved.tags);
+            assert_eq!(loaded.created_at, saved.created_at);
+            assert_eq!(loaded.updated_at, saved.updated_at);
+            assert_eq!(
+                *decrypt_password(cipher, &loaded.password).unwrap(),
+                *decrypt_password(cipher, &saved.password).unwrap()
+            );
+            assert_eq!(
+                *decrypt_notes(cipher, &loaded.notes).unwrap(),
+                *decrypt_notes(cipher, &saved.notes).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn vault_round_trips_unicode_commas_and_colons() {
+        let dir = test_dir("round-trip");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        let cipher = VaultCipher::new([7; 32]);
+        let entries = vec![
+            sample_entry(&cipher, "My, Bank"),
+            sample_entry(&cipher, "host:8080"),
+            sample_entry(&cipher, "Café 東京 🔐"),
+        ];
+
+        save_entries_with(path, &entries, &cipher, &OPTIONS).unwrap();
+        let loaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+
+        assert!(!loaded.updated);
+        assert!(!loaded.tampered);
+        assert!(loaded.corrupt.is_empty());
+        assert_same_entries(&loaded.entries, &entries, &cipher);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn csv_vault_is_upgraded_to_json_in_place() {
+        let dir = test_dir("csv-upgrade");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        let cipher = VaultCipher::new([7; 32]);
+        let password = encrypt_password(&cipher, "hunter2").unwrap();
+        let notes = encrypt_notes(&cipher, "a, b: c").unwrap();
+        let content = format!(
+            "{}\n{}{}\n\"My, Bank\",{},me,,{},1,2,,\"kerja,bank\",\nhost:8080,{},,,,1,2,,,\nCafé 東京 🔐,{},,,,1,2,,,\n",
+            format_header(CSV_FORMAT_VERSION, cipher.algorithm()),
+            VERIFY_HEADER_PREFIX,
+            encrypt_password(&cipher, VERIFY_SENTINEL).unwrap(),
+            password,
+            notes,
+            password,
+            password,
+        );
+        fs::write(path, content).unwrap();
+
+        let loaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+        assert!(loaded.updated);
+        assert!(loaded.corrupt.is_empty());
+        let accounts: Vec<&str> = loaded.entries.iter().map(|e| e.account.as_str()).collect();
+        assert_eq!(accounts, vec!["My, Bank", "host:8080", "Café 東京 🔐"]);
+        assert_eq!(loaded.entries[0].tags, vec!["kerja", "bank"]);
+
+        save_entries_with(path, &loaded.entries, &cipher, &OPTIONS).unwrap();
+        let saved = fs::read_to_string(path).unwrap();
+        assert_eq!(
+            read_first_line(path).unwrap(),
+            format_header(FORMAT_VERSION, cipher.algorithm())
+        );
+        assert!(saved.contains("\"account\": \"My, Bank\""));
+        let reloaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+        assert!(!reloaded.updated);
+        assert!(!reloaded.tampered);
+        assert_same_entries(&reloaded.entries, &loaded.entries, &cipher);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sealed_vault_round_trips_as_one_json_blob() {
+        let dir = test_dir("sealed");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        let cipher = VaultCipher::new([7; 32]);
+        let entries = vec![
+            sample_entry(&cipher, "My, Bank"),
+            sample_entry(&cipher, "Café 東京 🔐"),
+        ];
+        let sealed = StorageOptions {
+            sealed: Some(true),
+            ..OPTIONS
+        };
+
+        save_entries_with(path, &entries, &cipher, &sealed).unwrap();
+        assert!(!fs::read_to_string(path).unwrap().contains("Bank"));
+        assert!(is_sealed_vault(path));
+        let loaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+
+        assert!(!loaded.updated);
+        assert_same_entries(&loaded.entries, &entries, &cipher);
+        assert!(load_entries_with(path, &VaultCipher::new([8; 32]), &OPTIONS).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sealed_csv_vault_is_upgraded_and_stays_sealed() {
+        let dir = test_dir("sealed-csv");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        let cipher = VaultCipher::new([7; 32]);
+        let records = format!(
+            "\"My, Bank\",{},me,,\"plain: notes\",1,2,,,\n",
+            encrypt_password(&cipher, "hunter2").unwrap()
+        );
+        let blob = encrypt_password(&cipher, &records).unwrap();
+        let header = format_header(SEALED_CSV_VERSION, cipher.algorithm());
+        fs::write(path, format!("{}\n{}\n", header, blob)).unwrap();
+
+        let loaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+        assert!(loaded.updated);
+        assert_eq!(loaded.entries[0].account, "My, Bank");
+        assert_eq!(
+            *decrypt_notes(&cipher, &loaded.entries[0].notes).unwrap(),
+            "plain: notes"
+        );
+
+        save_entries_with(path, &loaded.entries, &cipher, &OPTIONS).unwrap();
+        assert_eq!(
+            read_first_line(path).unwrap(),
+            format_header(SEALED_FORMAT_VERSION, cipher.algorithm())
+        );
+        let reloaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+        assert!(!reloaded.updated);
+        assert_same_entries(&reloaded.entries, &loaded.entries, &cipher);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn bad_json_entries_are_reported_by_line() {
+        let dir = test_dir("json-corrupt");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        let cipher = VaultCipher::new([7; 32]);
+        let mut bad = sample_entry(&cipher, "bank");
+        bad.password = "abc:def:!!!".to_string();
+        let entries = vec![
+            sample_entry(&cipher, "gmail"),
+            bad,
+            sample_entry(&cipher, "github"),
+        ];
+        save_entries_with(path, &entries, &cipher, &OPTIONS).unwrap();
+        let content = fs::read_to_string(path).unwrap();
+        let (body, _) = split_mac_trailer(&content);
+        // An entry starts on its opening brace, the line above its account, so the 0-based
+        // index of the account line is the 1-based line of the entry.
+        let line_of = |account: &str| {
+            body.lines()
+                .position(|line| line.contains(&format!("\"account\": \"{}\"", account)))
+                .unwrap()
+        };
+        let (gmail_line, bank_line) = (line_of("gmail"), line_of("bank"));
+        let body = body.replacen("\"created_at\": 1700000000", "\"created_at\": \"x\"", 1);
+        fs::write(path, body).unwrap();
+
+        let loaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+
+        let accounts: Vec<&str> = loaded.entries.iter().map(|e| e.account.as_str()).collect();
+        assert_eq!(accounts, vec!["github"]);
+        let corrupt: Vec<(usize, &str)> = loaded
+            .corrupt
+            .iter()
+            .map(|c| (c.line, c.reason.as_str()))
+            .collect();
+        assert_eq!(
+            corrupt,
+            vec![
+                (gmail_line, "entri ke-1 tidak valid"),
+                (bank_line, "password 'bank' tidak valid"),
+            ]
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn headerless_vault_is_read_and_marked_for_upgrade() {
+        let dir = test_dir("legacy");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        let cipher = VaultCipher::new([7; 32]);
+        let password = encrypt_password(&cipher, "hunter2").unwrap();
+        fs::write(path, format!("gmail,{}\nbank,{}\n", password, password)).unwrap();
+
+        let loaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+
+        assert!(loaded.updated);
+        let accounts: Vec<&str> = loaded.entries.iter().map(|e| e.account.as_str()).collect();
+        assert_eq!(accounts, vec!["gmail", "bank"]);
+        assert_eq!(
+            *decrypt_password(&cipher, &loaded.entries[1].password).unwrap(),
+            "hunter2"
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saves_write_owner_only_and_keep_the_vault_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("permissions");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        let cipher = VaultCipher::new([7; 32]);
+        let entries = vec![sample_entry(&cipher, "gmail")];
+        let mode = |path: &str| fs::metadata(path).map(|m| m.permissions().mode() & 0o777);
+
+        save_entries_with(path, &entries, &cipher, &OPTIONS).unwrap();
+        assert_eq!(mode(path).unwrap(), 0o600);
+
+        fs::set_permissions(path, fs::Permissions::from_mode(0o640)).unwrap();
+        let mut tmp_mode = None;
+        replace_file_with(path, &OPTIONS, |tmp_path| {
+            write_entries(tmp_path, &entries, &cipher, false)?;
+            tmp_mode = Some(mode(tmp_path)?);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(tmp_mode, Some(0o600));
+        assert_eq!(mode(path).unwrap(), 0o640);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn fields_with_separators_round_trip_exactly() {
+        let dir = test_dir("separators");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        let cipher = VaultCipher::new([7; 32]);
+        let mut entry = sample_entry(&cipher, "x,\"y\":z\nsecond line");
+        entry.username = "user:name,with\"quotes\"".to_string();
+        entry.password = encrypt_password(&cipher, "p,a:s\ns\"w").unwrap();
+        entry.tags = vec!["a:b".to_string()];
+
+        save_entries_with(path, std::slice::from_ref(&entry), &cipher, &OPTIONS).unwrap();
+        let loaded = load_entries_with(path, &cipher, &OPTIONS).unwrap();
+
+        assert!(loaded.corrupt.is_empty());
+        assert_same_entries(&loaded.entries, &[entry], &cipher);
+        assert_eq!(
+            *decrypt_password(&cipher, &loaded.entries[0].password).unwrap(),
+            "p,a:s\ns\"w"
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn backups_within_one_second_are_all_kept_and_pruned_oldest_first() {
+        let dir = test_dir("backups");
+        let path = dir.join("vault.txt");
+        let path = path.to_str().unwrap();
+        for version in 0..7 {
+            fs::write(path, format!("version {}", version)).unwrap();
+            backup_vault(path).unwrap();
+        }
+
+        let backups = list_backups(path);
+        assert_eq!(backups.len(), 7);
+        assert_eq!(fs::read_to_string(&backups[0].1).unwrap(), "version 6");
+        assert_eq!(fs::read_to_string(&backups[6].1).unwrap(), "version 0");
+
+        prune_backups(path, 5);
+        let kept: Vec<String> = list_backups(path)
+            .iter()
+            .map(|(_, backup)| fs::read_to_string(backup).unwrap())
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                "version 6",
+                "version 5",
+                "version 4",
+                "version 3",
+                "version 2"
+            ]
+        );
+        assert_eq!(parse_backup_suffix("1700000000"), Some((1_700_000_000, 0)));
+        assert_eq!(
+            parse_backup_suffix("1700000000-12"),
+            Some((1_700_000_000, 12))
+        );
+        assert_eq!(parse_backup_suffix("1700000000.tmp"), None);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn password_digests_decrypt_once_until_pruned() {
+        let cipher = VaultCipher::new([7; 32]);
+        let other = VaultCipher::new([8; 32]);
+        let entry = sample_entry(&cipher, "bank");
+        let mut digests = PasswordDigests::default();
+        let digest = digests.digest(&cipher, &entry.password).unwrap();
+        assert_eq!(digest, cipher.secret_digest("hunter2").unwrap());
+        // A hit never decrypts, so even a cipher that cannot open the value gets the digest.
+        assert_eq!(digests.digest(&other, &entry.password).unwrap(), digest);
+        digests.retain(std::slice::from_ref(&entry));
+        assert!(digests.digest(&other, &entry.password).is_ok());
+        digests.retain(&[]);
+        assert!(digests.digest(&other, &entry.password).is_err());
+    }
+
+    #[test]
+    fn unterminated_quote_is_an_error() {
+        assert!(parse_csv_records("\"open,field\n").is_err());
+    }
+}